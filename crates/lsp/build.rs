@@ -0,0 +1,16 @@
+//! templates in `keywords/` into `$OUT_DIR/keywords.json`, embedded by `schema.rs`
+
+use std::{env, fs, path::Path};
+
+#[allow(dead_code)]
+#[path = "src/template.rs"]
+mod template;
+
+fn main() {
+    println!("cargo:rerun-if-changed=keywords");
+    println!("cargo:rerun-if-changed=src/template.rs");
+    let keywords = template::from_dir(Path::new("keywords")).expect("read keywords/");
+    let json = serde_json::to_string(&keywords).expect("serialize schema");
+    let out = Path::new(&env::var("OUT_DIR").unwrap()).join("keywords.json");
+    fs::write(out, json).expect("write keywords.json");
+}
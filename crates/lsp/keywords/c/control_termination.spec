@@ -0,0 +1,10 @@
+*CONTROL_TERMINATION
+about when the run stops
+endtim  F  [0,)  unit=time
+endcyc  I  [0,)
+dtmin   F  [0,)
+endeng  F  [0,)
+endmas  F  [0,)
+nosol   I
+    0   off
+    1   on
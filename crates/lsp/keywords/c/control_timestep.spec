@@ -0,0 +1,17 @@
+*CONTROL_TIMESTEP
+about time step size control and mass scaling
+dtinit  F  [0,)  unit=time
+tssfac  F  (0,1]  curve
+isdo    I
+    0   characteristic length by area/min(longest side, longest diagonal)
+    1   characteristic length by area/longest diagonal
+    2   characteristic length based on bar wave speed
+tslimt  F  [0,)  unit=time
+dt2ms   F  unit=time
+lctim   I  [0,)  ref=curve
+erode   I
+    0   no
+    1   delete solids and shells when DTMIN of *CONTROL_TERMINATION is reached
+ms1st   I
+    0   mass scaling throughout the run
+    1   mass scaling only at the first step
@@ -0,0 +1,8 @@
+*DATABASE_CPM_SENSOR
+about CPM airbag sensor output, `cpm_sensor`
+dt      F  [0,)  unit=time
+binary  I
+    0   default, ASCII file on SMP, binout on MPP
+    1   ASCII file
+    2   binout
+    3   both
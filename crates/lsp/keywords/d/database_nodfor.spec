@@ -0,0 +1,10 @@
+*DATABASE_NODFOR
+about nodal force groups output, `nodfor`
+dt      F  [0,)  unit=time
+binary  I
+    0   default, ASCII file on SMP, binout on MPP
+    1   ASCII file
+    2   binout
+    3   both
+lcur    I  [0,)
+ioopt   I
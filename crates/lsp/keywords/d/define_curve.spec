@@ -0,0 +1,15 @@
+*DEFINE_CURVE
+about a load curve, abscissa and ordinate pairs
+lcid    I  (0,)  def=curve
+sidr    I
+    0   transient analysis only
+    1   stress initialization only
+    2   both
+sfa     F
+sfo     F
+offa    F
+offo    F
+dattyp  I
+a1      F
+o1      F
+card 2  repeat
@@ -0,0 +1,7 @@
+*DEFINE_TABLE
+about a table of curves, one for each value
+tbid    I  (0,)  def=curve
+sfa     F
+offa    F
+value   F
+card 2  repeat
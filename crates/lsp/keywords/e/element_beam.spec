@@ -0,0 +1,3 @@
+*ELEMENT_BEAM
+about beam elements
+card 1  repeat
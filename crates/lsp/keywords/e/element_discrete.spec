@@ -0,0 +1,7 @@
+*ELEMENT_DISCRETE
+about discrete springs and dampers between two nodes
+eid     I  (0,)  def=element
+pid     ref=part
+n1      ref=node
+n2      ref=node
+card 1  repeat
@@ -0,0 +1,6 @@
+*ELEMENT_MASS
+about lumped masses on nodes
+eid     I  (0,)  def=element
+id      ref=node
+pid     ref=part
+card 1  repeat
@@ -0,0 +1,13 @@
+*ELEMENT_SHELL
+about shell elements, 3 or 4 nodes, 8 for quadratic ones
+eid     I  (0,)  def=element
+pid     ref=part
+n1      ref=node
+n2      ref=node
+n3      ref=node
+n4      ref=node
+n5      ref=node
+n6      ref=node
+n7      ref=node
+n8      ref=node
+card 1  repeat
@@ -0,0 +1,21 @@
+*HOURGLASS  repeat
+about hourglass control, for the parts naming it
+hgid    I  (0,)  def=hourglass
+ihq     I
+    0   default, 1
+    1   standard LS-DYNA viscous
+    2   Flanagan-Belytschko viscous
+    3   Flanagan-Belytschko viscous with exact volume integration
+    4   Flanagan-Belytschko stiffness
+    5   Flanagan-Belytschko stiffness with exact volume integration
+    6   Belytschko-Bindeman assumed strain co-rotational stiffness
+    7   linear total strain version of 6
+    8   warping stiffness for fully integrated shells
+    9   Puso enhanced assumed strain stiffness
+    10  Cosserat point element
+qm      F  [0,)
+ibq     I
+q1      F  [0,)
+q2      F  [0,)
+qb/vdc  F
+qw      F
@@ -0,0 +1,465 @@
+$ the IDs cards define and the IDs fields name, for keywords without a
+$ .spec of their own. def=what a field is the ID of, ref=what its value
+$ names, the first ref= whose `if` holds
+$
+$ materials
+*MAT_1DOF_GENERALIZED_SPRING
+mid     def=mat
+*MAT_CABLE_DISCRETE_BEAM
+mid     def=mat
+*MAT_DAMPER_NONLINEAR_VISCOUS
+mid     def=mat
+*MAT_DAMPER_VISCOUS
+mid     def=mat
+*MAT_ELASTIC_6DOF_SPRING_DISCRETE_BEAM
+mid     def=mat
+*MAT_ELASTIC_SPRING_DISCRETE_BEAM
+mid     def=mat
+*MAT_GENERAL_JOINT_DISCRETE_BEAM
+mid     def=mat
+*MAT_GENERAL_NONLINEAR_1DOF_DISCRETE_BEAM
+mid     def=mat
+*MAT_GENERAL_NONLINEAR_6DOF_DISCRETE_BEAM
+mid     def=mat
+*MAT_GENERAL_SPRING_DISCRETE_BEAM
+mid     def=mat
+*MAT_HYDRAULIC_GAS_DAMPER_DISCRETE_BEAM
+mid     def=mat
+*MAT_INELASTIC_6DOF_SPRING_DISCRETE_BEAM
+mid     def=mat
+*MAT_INELASTIC_SPRING_DISCRETE_BEAM
+mid     def=mat
+*MAT_LINEAR_ELASTIC_DISCRETE_BEAM
+mid     def=mat
+*MAT_NONLINEAR_ELASTIC_DISCRETE_BEAM
+mid     def=mat
+*MAT_NONLINEAR_PLASTIC_DISCRETE_BEAM
+mid     def=mat
+*MAT_NULL
+mid     def=mat
+*MAT_RESULTANT_PLASTICITY
+mid     def=mat
+*MAT_SAMP-1
+mid     def=mat
+*MAT_SAMP_LIGHT
+mid     def=mat
+*MAT_SEATBELT
+mid     def=mat
+*MAT_SID_DAMPER_DISCRETE_BEAM
+mid     def=mat
+*MAT_SPRING_ELASTIC
+mid     def=mat
+*MAT_SPRING_NONLINEAR_ELASTIC
+mid     def=mat
+*MAT_ADD_EROSION
+mid     ref=mat
+*MAT_ADD_AIRBAG_POROSITY_LEAKAGE
+mid     ref=mat
+$
+$ sections
+*SECTION_BEAM
+secid   def=section
+*SECTION_DISCRETE
+secid   def=section
+*SECTION_SEATBELT
+secid   def=section
+$
+$ curves, tables and functions
+*DEFINE_CURVE_DUPLICATE
+lcid    def=curve
+rlcid   ref=curve
+*DEFINE_CURVE_FUNCTION
+lcid    def=curve
+*DEFINE_CURVE_SMOOTH
+lcid    def=curve
+*DEFINE_FUNCTION
+fid     def=curve
+*DEFINE_FUNCTION_TABULATED
+fid     def=curve
+*DEFINE_TABLE_2D
+tbid    def=curve
+curveid ref=curve
+*DEFINE_TABLE_3D
+tbid    def=curve
+tableid ref=curve
+$
+$ boxes, coordinate systems and vectors
+*DEFINE_BOX
+boxid   def=box
+*DEFINE_BOX_LOCAL
+boxid   def=box
+*DEFINE_COORDINATE_NODES
+cid     def=coord
+n1      ref=node
+n2      ref=node
+n3      ref=node
+*DEFINE_COORDINATE_SYSTEM
+cid     def=coord
+*DEFINE_COORDINATE_VECTOR
+cid     def=coord
+nid     ref=node
+*DEFINE_VECTOR
+vid     def=vector
+cid     ref=coord
+*DEFINE_VECTOR_NODES
+vid     def=vector
+nodet   ref=node
+nodeh   ref=node
+$
+$ elements
+*ELEMENT_INERTIA
+eid     def=element
+nid     ref=node
+cid     ref=coord
+*ELEMENT_MASS_PART
+id      ref=part
+lcid    ref=curve
+*ELEMENT_MASS_PART_SET
+id      ref=set_part
+lcid    ref=curve
+*ELEMENT_SEATBELT
+eid     def=element
+pid     ref=part
+n1      ref=node
+n2      ref=node
+n3      ref=node
+n4      ref=node
+*ELEMENT_SEATBELT_ACCELEROMETER
+nid1    ref=node
+nid2    ref=node
+nid3    ref=node
+*DEFINE_ELEMENT_DEATH_SHELL
+eid     ref=element
+boxid   ref=box
+*DEFINE_ELEMENT_DEATH_SOLID
+eid     ref=element
+boxid   ref=box
+*DEFINE_ELEMENT_DEATH_BEAM
+eid     ref=element
+boxid   ref=box
+*DEFINE_ELEMENT_DEATH_SHELL_SET
+sid     ref=set_shell
+boxid   ref=box
+*DEFINE_ELEMENT_DEATH_SOLID_SET
+sid     ref=set_solid
+boxid   ref=box
+*DEFINE_ELEMENT_DEATH_BEAM_SET
+sid     ref=set_beam
+boxid   ref=box
+$
+$ sets
+*SET_BEAM
+sid     def=set_beam
+eid1    ref=element
+eid2    ref=element
+eid3    ref=element
+eid4    ref=element
+eid5    ref=element
+eid6    ref=element
+eid7    ref=element
+eid8    ref=element
+*SET_BEAM_ADD
+sid     def=set_beam
+bsid1   ref=set_beam
+bsid2   ref=set_beam
+bsid3   ref=set_beam
+bsid4   ref=set_beam
+bsid5   ref=set_beam
+bsid6   ref=set_beam
+bsid7   ref=set_beam
+bsid8   ref=set_beam
+*SET_BEAM_GENERAL
+sid     def=set_beam
+*SET_BEAM_GENERATE
+sid     def=set_beam
+*SET_BEAM_INTERSECT
+sid     def=set_beam
+bsid1   ref=set_beam
+bsid2   ref=set_beam
+bsid3   ref=set_beam
+bsid4   ref=set_beam
+bsid5   ref=set_beam
+bsid6   ref=set_beam
+bsid7   ref=set_beam
+bsid8   ref=set_beam
+*SET_BEAM_GENERATE_INCREMENT
+sid     def=set_beam
+*SET_DISCRETE
+sid     def=set_discrete
+eid1    ref=element
+eid2    ref=element
+eid3    ref=element
+eid4    ref=element
+eid5    ref=element
+eid6    ref=element
+eid7    ref=element
+eid8    ref=element
+*SET_DISCRETE_ADD
+sid     def=set_discrete
+dsid1   ref=set_discrete
+dsid2   ref=set_discrete
+dsid3   ref=set_discrete
+dsid4   ref=set_discrete
+dsid5   ref=set_discrete
+dsid6   ref=set_discrete
+dsid7   ref=set_discrete
+dsid8   ref=set_discrete
+*SET_DISCRETE_GENERAL
+sid     def=set_discrete
+*SET_DISCRETE_GENERATE
+sid     def=set_discrete
+*SET_NODE_ADD
+sid     def=set_node
+nsid1   ref=set_node
+nsid2   ref=set_node
+nsid3   ref=set_node
+nsid4   ref=set_node
+nsid5   ref=set_node
+nsid6   ref=set_node
+nsid7   ref=set_node
+nsid8   ref=set_node
+*SET_NODE_ADD_ADVANCED
+sid     def=set_node
+*SET_NODE_COLUMN
+sid     def=set_node
+nid     ref=node
+*SET_NODE_GENERAL
+sid     def=set_node
+*SET_NODE_INTERSECT
+sid     def=set_node
+nsid1   ref=set_node
+nsid2   ref=set_node
+nsid3   ref=set_node
+nsid4   ref=set_node
+nsid5   ref=set_node
+nsid6   ref=set_node
+nsid7   ref=set_node
+nsid8   ref=set_node
+*SET_NODE_LIST_GENERATE
+sid     def=set_node
+*SET_NODE_LIST_GENERATE_INCREMENT
+sid     def=set_node
+*SET_PART_ADD
+sid     def=set_part
+psid1   ref=set_part
+psid2   ref=set_part
+psid3   ref=set_part
+psid4   ref=set_part
+psid5   ref=set_part
+psid6   ref=set_part
+psid7   ref=set_part
+psid8   ref=set_part
+*SET_PART_COLUMN
+sid     def=set_part
+pid     ref=part
+*SET_PART_LIST_GENERATE
+sid     def=set_part
+*SET_SEGMENT_ADD
+sid     def=set_segment
+ssid1   ref=set_segment
+ssid2   ref=set_segment
+ssid3   ref=set_segment
+ssid4   ref=set_segment
+ssid5   ref=set_segment
+ssid6   ref=set_segment
+ssid7   ref=set_segment
+ssid8   ref=set_segment
+*SET_SEGMENT_GENERAL
+sid     def=set_segment
+*SET_SEGMENT_INTERSECT
+sid     def=set_segment
+ssid1   ref=set_segment
+ssid2   ref=set_segment
+ssid3   ref=set_segment
+ssid4   ref=set_segment
+ssid5   ref=set_segment
+ssid6   ref=set_segment
+ssid7   ref=set_segment
+ssid8   ref=set_segment
+*SET_SHELL_ADD
+sid     def=set_shell
+ssid1   ref=set_shell
+ssid2   ref=set_shell
+ssid3   ref=set_shell
+ssid4   ref=set_shell
+ssid5   ref=set_shell
+ssid6   ref=set_shell
+ssid7   ref=set_shell
+ssid8   ref=set_shell
+*SET_SHELL_COLUMN
+sid     def=set_shell
+eid     ref=element
+*SET_SHELL_GENERAL
+sid     def=set_shell
+*SET_SHELL_INTERSECT
+sid     def=set_shell
+ssid1   ref=set_shell
+ssid2   ref=set_shell
+ssid3   ref=set_shell
+ssid4   ref=set_shell
+ssid5   ref=set_shell
+ssid6   ref=set_shell
+ssid7   ref=set_shell
+ssid8   ref=set_shell
+*SET_SHELL_LIST_GENERATE
+sid     def=set_shell
+*SET_SHELL_LIST_GENERATE_INCREMENT
+sid     def=set_shell
+*SET_SOLID_ADD
+sid     def=set_solid
+ssid1   ref=set_solid
+ssid2   ref=set_solid
+ssid3   ref=set_solid
+ssid4   ref=set_solid
+ssid5   ref=set_solid
+ssid6   ref=set_solid
+ssid7   ref=set_solid
+ssid8   ref=set_solid
+*SET_SOLID_GENERAL
+sid     def=set_solid
+*SET_SOLID_GENERATE
+sid     def=set_solid
+*SET_SOLID_GENERATE_INCREMENT
+sid     def=set_solid
+*SET_SOLID_INTERSECT
+sid     def=set_solid
+ssid1   ref=set_solid
+ssid2   ref=set_solid
+ssid3   ref=set_solid
+ssid4   ref=set_solid
+ssid5   ref=set_solid
+ssid6   ref=set_solid
+ssid7   ref=set_solid
+ssid8   ref=set_solid
+$
+$ nodes
+*NODE_MERGE_SET
+nsid    ref=set_node
+*NODE_TRANSFORM
+nsid    ref=set_node
+$
+$ contacts
+*CONTACT_AIRBAG_SINGLE_SURFACE
+ssid    ref=set_segment if sstyp=0  ref=set_shell if sstyp=1  ref=set_part if sstyp=2,6  ref=part if sstyp=3  ref=set_node if sstyp=4
+msid    ref=set_segment if mstyp=0  ref=set_shell if mstyp=1  ref=set_part if mstyp=2,6  ref=part if mstyp=3  ref=set_node if mstyp=4
+sboxid  ref=box
+mboxid  ref=box
+*CONTACT_AUTOMATIC_GENERAL_INTERIOR
+ssid    ref=set_segment if sstyp=0  ref=set_shell if sstyp=1  ref=set_part if sstyp=2,6  ref=part if sstyp=3  ref=set_node if sstyp=4
+msid    ref=set_segment if mstyp=0  ref=set_shell if mstyp=1  ref=set_part if mstyp=2,6  ref=part if mstyp=3  ref=set_node if mstyp=4
+sboxid  ref=box
+mboxid  ref=box
+*CONTACT_AUTOMATIC_SINGLE_SURFACE
+ssid    ref=set_segment if sstyp=0  ref=set_shell if sstyp=1  ref=set_part if sstyp=2,6  ref=part if sstyp=3  ref=set_node if sstyp=4
+msid    ref=set_segment if mstyp=0  ref=set_shell if mstyp=1  ref=set_part if mstyp=2,6  ref=part if mstyp=3  ref=set_node if mstyp=4
+sboxid  ref=box
+mboxid  ref=box
+*CONTACT_AUTOMATIC_SURFACE_TO_SURFACE
+ssid    ref=set_segment if sstyp=0  ref=set_shell if sstyp=1  ref=set_part if sstyp=2,6  ref=part if sstyp=3  ref=set_node if sstyp=4
+msid    ref=set_segment if mstyp=0  ref=set_shell if mstyp=1  ref=set_part if mstyp=2,6  ref=part if mstyp=3  ref=set_node if mstyp=4
+sboxid  ref=box
+mboxid  ref=box
+*CONTACT_FORCE_TRANSDUCER_PENALTY
+ssid    ref=set_segment if sstyp=0  ref=set_shell if sstyp=1  ref=set_part if sstyp=2,6  ref=part if sstyp=3  ref=set_node if sstyp=4
+msid    ref=set_segment if mstyp=0  ref=set_shell if mstyp=1  ref=set_part if mstyp=2,6  ref=part if mstyp=3  ref=set_node if mstyp=4
+sboxid  ref=box
+mboxid  ref=box
+*CONTACT_TIED_SHELL_EDGE_TO_SURFACE_BEAM_OFFSET
+ssid    ref=set_segment if sstyp=0  ref=set_shell if sstyp=1  ref=set_part if sstyp=2,6  ref=part if sstyp=3  ref=set_node if sstyp=4
+msid    ref=set_segment if mstyp=0  ref=set_shell if mstyp=1  ref=set_part if mstyp=2,6  ref=part if mstyp=3  ref=set_node if mstyp=4
+sboxid  ref=box
+mboxid  ref=box
+*CONTACT_INTERIOR
+psid    ref=set_part
+$
+$ loads and boundary conditions
+*LOAD_BODY_Z
+lcid    ref=curve
+lciddr  ref=curve
+cid     ref=coord
+*LOAD_NODE_POINT
+nid     ref=node
+lcid    ref=curve
+cid     ref=coord
+m1      ref=node
+m2      ref=node
+m3      ref=node
+*LOAD_NODE_SET
+nsid    ref=set_node
+lcid    ref=curve
+cid     ref=coord
+m1      ref=node
+m2      ref=node
+m3      ref=node
+*LOAD_RIGID_BODY
+pid     ref=part
+lcid    ref=curve
+cid     ref=coord
+m1      ref=node
+m2      ref=node
+m3      ref=node
+*BOUNDARY_PRESCRIBED_MOTION_NODE
+typeid  ref=node
+lcid    ref=curve
+vid     ref=vector
+*BOUNDARY_PRESCRIBED_MOTION_SET
+typeid  ref=set_node
+lcid    ref=curve
+vid     ref=vector
+*BOUNDARY_PRESCRIBED_MOTION_RIGID
+typeid  ref=part
+lcid    ref=curve
+vid     ref=vector
+*BOUNDARY_SPC_NODE
+nid     ref=node
+cid     ref=coord
+*BOUNDARY_SPC_SET
+nsid    ref=set_node
+cid     ref=coord
+$
+$ output
+*DATABASE_HISTORY_NODE
+id1     ref=node
+id2     ref=node
+id3     ref=node
+id4     ref=node
+id5     ref=node
+id6     ref=node
+id7     ref=node
+id8     ref=node
+*DATABASE_HISTORY_SHELL
+id1     ref=element
+id2     ref=element
+id3     ref=element
+id4     ref=element
+id5     ref=element
+id6     ref=element
+id7     ref=element
+id8     ref=element
+*DATABASE_HISTORY_SOLID
+id1     ref=element
+id2     ref=element
+id3     ref=element
+id4     ref=element
+id5     ref=element
+id6     ref=element
+id7     ref=element
+id8     ref=element
+*DATABASE_HISTORY_BEAM
+id1     ref=element
+id2     ref=element
+id3     ref=element
+id4     ref=element
+id5     ref=element
+id6     ref=element
+id7     ref=element
+id8     ref=element
+*DATABASE_HISTORY_DISCRETE
+id1     ref=element
+id2     ref=element
+id3     ref=element
+id4     ref=element
+id5     ref=element
+id6     ref=element
+id7     ref=element
+id8     ref=element
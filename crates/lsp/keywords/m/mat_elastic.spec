@@ -0,0 +1,9 @@
+*MAT_ELASTIC
+about isotropic elastic material, type 1
+mid     I  (0,)  def=mat
+ro      F  (0,)  unit=density
+e       F  (0,)  unit=stress
+pr      F  [0,0.5)
+da      F  [0,)
+db      F  [0,)
+k       F  [0,)  unit=stress
@@ -0,0 +1,17 @@
+*MAT_PIECEWISE_LINEAR_PLASTICITY
+about elasto-plastic material with a stress strain curve, type 24
+mid     I  (0,)  def=mat
+ro      F  (0,)  unit=density
+e       F  (0,)  unit=stress
+pr      F  [0,0.5)
+sigy    F  [0,)  unit=stress
+etan    F  [0,)  unit=stress
+fail    F
+tdel    F  [0,)  unit=time
+c       F  [0,)  unit=1/time
+p       F  [0,)
+lcss    I  [0,)  ref=curve
+lcsr    I  [0,)  ref=curve
+vp      F
+    0   scale yield stress
+    1   viscoplastic formulation
@@ -0,0 +1,15 @@
+*MAT_PLASTIC_KINEMATIC
+about bilinear elasto-plastic material with kinematic and isotropic hardening, type 3
+mid     I  (0,)  def=mat
+ro      F  (0,)  unit=density
+e       F  (0,)  unit=stress
+pr      F  [0,0.5)
+sigy    F  [0,)  unit=stress
+etan    F  [0,)  unit=stress
+beta    F  [0,1]
+src     F  [0,)  unit=1/time
+srp     F  [0,)
+fs      F
+vp      F
+    0   scale yield stress
+    1   viscoplastic formulation
@@ -0,0 +1,16 @@
+*MAT_RIGID
+about rigid body material, type 20
+mid     I  (0,)  def=mat
+ro      F  (0,)  unit=density
+e       F  (0,)  unit=stress
+pr      F  [0,0.5)
+n       F
+couple  F
+m       F
+alias   A
+cmo     F
+    -1  constraints in the local system of CON1
+    0   no constraints
+    1   constraints in the global system
+con1    F
+con2    F
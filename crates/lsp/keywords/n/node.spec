@@ -0,0 +1,25 @@
+*NODE
+about nodes and their coordinates
+nid     I  (0,)  def=node
+x       F  unit=length
+y       F  unit=length
+z       F  unit=length
+tc      I
+    0   no constraints
+    1   x
+    2   y
+    3   z
+    4   x and y
+    5   y and z
+    6   z and x
+    7   x, y and z
+rc      I
+    0   no constraints
+    1   x
+    2   y
+    3   z
+    4   x and y
+    5   y and z
+    6   z and x
+    7   x, y and z
+card 1  repeat
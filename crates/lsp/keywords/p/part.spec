@@ -0,0 +1,15 @@
+*PART  repeat
+about a part, its section, material, hourglass control and EOS
+pid     I  (0,)  def=part
+secid   I  (0,)  ref=section
+mid     I  (0,)  ref=mat
+eosid   I  [0,)  ref=eos
+hgid    I  [0,)  ref=hourglass
+grav    I
+    0   gravity on all parts
+    1   gravity on this part only
+adpopt  I
+    0   no adaptivity
+    1   h-adaptive
+    2   r-adaptive remeshing
+tmid    I  [0,)
@@ -0,0 +1,55 @@
+*SECTION_SHELL  repeat
+about shell element formulation and thickness
+secid   I  (0,)  def=section
+elform  I  open
+    -16 fully integrated shell, modified for higher accuracy
+    0   default, 2
+    1   Hughes-Liu
+    2   Belytschko-Tsay
+    3   BCIZ triangular
+    4   C0 triangular
+    5   Belytschko-Tsay membrane
+    6   S/R Hughes-Liu
+    7   S/R co-rotational Hughes-Liu
+    8   Belytschko-Leviathan
+    9   fully integrated Belytschko-Tsay membrane
+    10  Belytschko-Wong-Chiang
+    11  fast co-rotational Hughes-Liu
+    12  plane stress (x-y plane)
+    13  plane strain (x-y plane)
+    14  axisymmetric solid, area weighted
+    15  axisymmetric solid, volume weighted
+    16  fully integrated shell
+    17  fully integrated DKT triangular
+    18  fully integrated linear DK quadrilateral/triangular
+    20  fully integrated linear assumed strain C0
+    21  fully integrated linear assumed strain C0, 5 dof
+    23  8-node quadratic quadrilateral
+    24  6-node quadratic triangle
+    25  Belytschko-Tsay with thickness stretch
+    26  fully integrated with thickness stretch
+    27  C0 triangular with thickness stretch
+shrf    F  (0,)
+nip     I  [0,100]
+propt   F
+    1   average resultant and fiber lengths
+    2   average lengths
+    3   no averaging
+qr/irid F
+icomp   I
+    0   no
+    1   material angle per integration point, cards follow
+setyp   I
+    1   2D solid, plane strain or axisymmetric
+    2   2D shell, plane stress
+    3   2D beam
+t1      F  [0,)  unit=length
+t2      F  [0,)  unit=length
+t3      F  [0,)  unit=length
+t4      F  [0,)  unit=length
+nloc    F  [-1,1]
+marea   F  [0,)  unit=mass/area
+idof    F
+edgset  I
+bi      F
+card 3  if icomp=1
@@ -0,0 +1,31 @@
+*SECTION_SOLID  repeat
+about solid element formulation
+secid   I  (0,)  def=section
+elform  I  open
+    -2  fully integrated S/R, accurate for poor aspect ratios
+    -1  fully integrated S/R, efficient for poor aspect ratios
+    0   1 point co-rotational for *MAT_MODIFIED_HONEYCOMB
+    1   constant stress
+    2   fully integrated S/R
+    3   fully integrated quadratic 8 node with nodal rotations
+    4   S/R quadratic tetrahedron with nodal rotations
+    5   1 point ALE
+    6   1 point Eulerian
+    7   1 point Eulerian ambient
+    8   acoustic
+    9   1 point co-rotational for *MAT_MODIFIED_HONEYCOMB
+    10  1 point tetrahedron
+    11  1 point ALE multi-material
+    12  1 point, single material and void
+    13  1 point nodal pressure tetrahedron
+    14  8 point acoustic
+    15  2 point pentahedron
+    16  4 or 5 point 10-noded tetrahedron
+    17  10-noded composite tetrahedron
+    18  8 point enhanced strain, linear only
+    19  8 point 4-node cohesive
+    20  8 point 4-node cohesive with offsets for shells
+    21  6 point 6-node cohesive
+    22  6 point 6-node cohesive with offsets for shells
+    23  20-node hexahedron
+aet     I
@@ -0,0 +1,12 @@
+*SET_NODE_LIST
+about a set of nodes
+sid     I  (0,)  def=set_node
+nid1    ref=node
+nid2    ref=node
+nid3    ref=node
+nid4    ref=node
+nid5    ref=node
+nid6    ref=node
+nid7    ref=node
+nid8    ref=node
+card 2  repeat
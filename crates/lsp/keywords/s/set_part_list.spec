@@ -0,0 +1,12 @@
+*SET_PART_LIST
+about a set of parts
+sid     I  (0,)  def=set_part
+pid1    ref=part
+pid2    ref=part
+pid3    ref=part
+pid4    ref=part
+pid5    ref=part
+pid6    ref=part
+pid7    ref=part
+pid8    ref=part
+card 2  repeat
@@ -0,0 +1,8 @@
+*SET_SEGMENT
+about a set of segments, 3 or 4 nodes each
+sid     I  (0,)  def=set_segment
+n1      ref=node
+n2      ref=node
+n3      ref=node
+n4      ref=node
+card 2  repeat
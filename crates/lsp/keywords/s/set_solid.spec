@@ -0,0 +1,12 @@
+*SET_SOLID
+about a set of solid elements
+sid     I  (0,)  def=set_solid
+eid1    ref=element
+eid2    ref=element
+eid3    ref=element
+eid4    ref=element
+eid5    ref=element
+eid6    ref=element
+eid7    ref=element
+eid8    ref=element
+card 2  repeat
@@ -1,14 +1,19 @@
-use std::fs;
+use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 
-use lsp::helper::{range, user_edit};
-use lsp::ir::{compile, Diagnostics, Diff, SourceProgram};
-use lsp::line_index::LineIndex;
-use lsp::{Db, RootDatabase};
+use lsp::helper::{location, offset, path, range, uri};
+use lsp::complete::complete;
+use lsp::hover::hover;
+use lsp::index::Allow;
+use lsp::ir::{compile, parse, Diagnostics};
+use lsp::nav;
+use lsp::outline::outline;
+use lsp::schema;
+use lsp::RootDatabase;
 
 use serde_json::Value;
 
-use text_edit::Indel;
 use tower_lsp::jsonrpc::{Error, Result};
 use tower_lsp::lsp_types::*;
 use tower_lsp::{Client, LanguageServer, LspService, Server};
@@ -17,29 +22,99 @@ use tower_lsp::{Client, LanguageServer, LspService, Server};
 struct GlobalState {
     client: Client,
     pub(crate) analysis_host: Arc<Mutex<RootDatabase>>,
+    /// `--keywords` on the command line
+    keyword_dirs: Vec<PathBuf>,
+    /// workspace folder, settings are relative to it
+    root: Mutex<Option<Url>>,
 }
 
 impl GlobalState {
-    fn new(client: Client) -> Self {
+    fn new(client: Client, keyword_dirs: Vec<PathBuf>) -> Self {
         Self {
             client,
             analysis_host: Arc::new(Mutex::new(RootDatabase::new())),
+            keyword_dirs,
+            root: Mutex::new(None),
         }
     }
     fn db(&self) -> std::sync::MutexGuard<'_, lsp::RootDatabase> {
         self.analysis_host.lock().unwrap()
     }
+
+    /// `DBK_KEYWORDS`, then the editor's, then the command line's, later wins
+    async fn load_keywords(&self, workspace: Vec<PathBuf>) {
+        let mut dirs = schema::env_dirs();
+        dirs.extend(workspace);
+        dirs.extend(self.keyword_dirs.iter().cloned());
+        let problems = self.db().load_keywords(&dirs);
+        for p in problems {
+            self.client.show_message(MessageType::WARNING, p).await;
+        }
+    }
+
+    async fn allow_unused(&self, options: Option<&Value>) {
+        let (allow, wrong) = allow_unused(options);
+        self.db().allow_unused(allow);
+        for w in wrong {
+            self.client.show_message(MessageType::WARNING, w).await;
+        }
+    }
+
+    async fn publish_diagnostics(&self, uri: Url) {
+        let diags = {
+            let mut db = self.db();
+            let source = db.input(&path(&uri));
+            let main = db.main_of(source);
+            compile(&*db, main, source);
+            compile::accumulated::<Diagnostics>(&*db, main, source)
+        };
+        let diags = diags.into_iter().map(|d| d.0).collect();
+        self.client.publish_diagnostics(uri, diags, None).await;
+    }
+}
+
+/// `{"keywords": ["dir", ..]}`, relative to the workspace
+fn keyword_dirs(options: Option<&Value>, root: Option<&Url>) -> Vec<PathBuf> {
+    let Some(Value::Array(dirs)) = options.and_then(|o| o.get("keywords")) else {
+        return vec![];
+    };
+    let root = root.and_then(|r| r.to_file_path().ok());
+    dirs.iter()
+        .filter_map(|d| d.as_str())
+        .map(|d| match &root {
+            Some(root) => root.join(d),
+            None => PathBuf::from(d),
+        })
+        .collect()
+}
+
+/// `{"unused": {"allow": ["curve", "mat 100-199", ..]}}`, and what's wrong in it
+fn allow_unused(options: Option<&Value>) -> (Vec<Allow>, Vec<String>) {
+    let allow = options.and_then(|o| o.pointer("/unused/allow")).and_then(|a| a.as_array());
+    let (mut allowed, mut wrong) = (vec![], vec![]);
+    for entry in allow.into_iter().flatten() {
+        match entry.as_str().and_then(Allow::parse) {
+            Some(a) => allowed.push(a),
+            None => wrong.push(format!("`unused.allow`: what is {entry}?")),
+        }
+    }
+    (allowed, wrong)
 }
 
 #[tower_lsp::async_trait]
 impl LanguageServer for GlobalState {
-    async fn initialize(&self, _: InitializeParams) -> Result<InitializeResult> {
+    #[allow(deprecated)]
+    async fn initialize(&self, params: InitializeParams) -> Result<InitializeResult> {
+        *self.root.lock().unwrap() = params.root_uri.clone();
+        let dirs = keyword_dirs(params.initialization_options.as_ref(), params.root_uri.as_ref());
+        self.load_keywords(dirs).await;
+        self.allow_unused(params.initialization_options.as_ref()).await;
         Ok(InitializeResult {
             server_info: None,
             capabilities: ServerCapabilities {
                 text_document_sync: Some(TextDocumentSyncCapability::Options(
                     TextDocumentSyncOptions {
-                        open_close: None,
+                        open_close: Some(true),
                         change: Some(TextDocumentSyncKind::INCREMENTAL),
                         will_save: None,
                         will_save_wait_until: None,
@@ -49,6 +124,15 @@ impl LanguageServer for GlobalState {
                     },
                 )),
                 hover_provider: Some(HoverProviderCapability::Simple(true)),
+                definition_provider: Some(OneOf::Left(true)),
+                references_provider: Some(OneOf::Left(true)),
+                document_highlight_provider: Some(OneOf::Left(true)),
+                rename_provider: Some(OneOf::Right(RenameOptions {
+                    prepare_provider: Some(true),
+                    work_done_progress_options: WorkDoneProgressOptions {
+                        work_done_progress: None,
+                    },
+                })),
                 color_provider: Some(ColorProviderCapability::Simple(true)),
                 document_symbol_provider: Some(OneOf::Right(DocumentSymbolOptions {
                     label: Some("lable".to_string()),
@@ -57,7 +141,7 @@ impl LanguageServer for GlobalState {
                     },
                 })),
                 completion_provider: Some(CompletionOptions {
-                    resolve_provider: Some(true),
+                    resolve_provider: None,
                     trigger_characters: Some(vec!["*".to_string()]),
                     all_commit_characters: None,
                     work_done_progress_options: WorkDoneProgressOptions {
@@ -73,7 +157,6 @@ impl LanguageServer for GlobalState {
                 }),
                 ..ServerCapabilities::default()
             },
-            ..Default::default()
         })
     }
 
@@ -82,25 +165,37 @@ impl LanguageServer for GlobalState {
             text_document,
             text: _,
         } = params;
-        let uri = text_document.uri;
-        let source = self.db().input(uri.path());
-        // again, async issue
-        // salsa input
-        compile(&*self.db(), source, None);
-        let diags = compile::accumulated::<Diagnostics>(&*self.db(), source, None);
-        self.client.publish_diagnostics(uri, diags, None).await;
+        self.db().refresh(&path(&text_document.uri));
+        self.publish_diagnostics(text_document.uri).await;
+    }
+
+    async fn initialized(&self, _: InitializedParams) {
+        // included files change on disk, not in the editor
+        let watchers = vec![FileSystemWatcher { glob_pattern: "**/*".to_string(), kind: None }];
+        let registration = Registration {
+            id: "watched-files".to_string(),
+            method: "workspace/didChangeWatchedFiles".to_string(),
+            register_options: serde_json::to_value(DidChangeWatchedFilesRegistrationOptions { watchers }).ok(),
+        };
+        if let Err(e) = self.client.register_capability(vec![registration]).await {
+            self.client.log_message(MessageType::WARNING, format!("files on disk aren't watched: {e}")).await;
+        }
+    }
+
+    async fn did_change_watched_files(&self, params: DidChangeWatchedFilesParams) {
+        for change in &params.changes {
+            self.db().refresh(&path(&change.uri));
+        }
+        let opened = self.db().opened();
+        for file in opened.iter().filter_map(|p| uri(p)) {
+            self.publish_diagnostics(file).await;
+        }
     }
 
-    // XXX
     async fn did_open(&self, params: DidOpenTextDocumentParams) {
-        let uri = params.text_document.uri;
-        let source = self.db().input(uri.path());
-        compile(&*self.db(), source, None);
-        let diags = compile::accumulated::<Diagnostics>(&*self.db(), source, None);
-        self.client.publish_diagnostics(uri, diags, None).await;
-        self.client
-            .log_message(MessageType::INFO, "file opened!")
-            .await;
+        let TextDocumentItem { uri, text, .. } = params.text_document;
+        self.db().open(&path(&uri), text);
+        self.publish_diagnostics(uri).await;
     }
 
     async fn did_change(&self, params: DidChangeTextDocumentParams) {
@@ -108,36 +203,154 @@ impl LanguageServer for GlobalState {
             text_document: VersionedTextDocumentIdentifier { uri, version: _ },
             content_changes,
         } = params;
+        let changed = self.db().change(&path(&uri), content_changes);
+        if let Err(e) = changed {
+            self.client.log_message(MessageType::ERROR, e.to_string()).await;
+            let msg = format!("{}, close and open it again", path(&uri));
+            self.client.show_message(MessageType::ERROR, msg).await;
+        }
+        self.publish_diagnostics(uri).await;
+    }
 
-        let edit = Diff::new(&*self.db(), content_changes);
-        let source = self.db().input(uri.path());
-        compile(&*self.db(), source, Some(edit));
-        let diags = lsp::ir::compile::accumulated::<Diagnostics>(&*self.db(), source, Some(edit));
-        self.client.publish_diagnostics(uri, diags, None).await;
+    async fn did_close(&self, params: DidCloseTextDocumentParams) {
+        let uri = params.text_document.uri;
+        self.db().close(&path(&uri));
+        self.client.publish_diagnostics(uri, vec![], None).await;
+    }
+
+    async fn did_change_configuration(&self, params: DidChangeConfigurationParams) {
+        // settings of the `dbk` section
+        let settings = params.settings.get("dbk").unwrap_or(&params.settings);
+        if settings.get("keywords").is_some() {
+            let root = self.root.lock().unwrap().clone();
+            self.load_keywords(keyword_dirs(Some(settings), root.as_ref())).await;
+        }
+        if settings.get("unused").is_some() {
+            self.allow_unused(Some(settings)).await;
+        }
     }
 
     async fn shutdown(&self) -> Result<()> {
         Ok(())
     }
 
-    async fn completion(&self, _params: CompletionParams) -> Result<Option<CompletionResponse>> {
-        let item = CompletionItem::new_simple("new".to_string(), "sim".to_string());
-        let remains = vec![item];
-        let res = CompletionResponse::Array(remains);
-        Ok(Some(res))
+    async fn completion(&self, params: CompletionParams) -> Result<Option<CompletionResponse>> {
+        let TextDocumentPositionParams { text_document, position } = params.text_document_position;
+        let mut db = self.db();
+        let source = db.input(&path(&text_document.uri));
+        let lines = parse(&*db, source).lines(&*db);
+        let Ok(offset) = offset(lines, position) else {return Ok(None)};
+        let items = complete(&*db, db.main_of(source), source, offset);
+        let items = items.into_iter().enumerate().map(|(i, item)| CompletionItem {
+            kind: Some(match item.snippet {
+                true => CompletionItemKind::KEYWORD,
+                false => CompletionItemKind::VALUE,
+            }),
+            detail: item.detail,
+            // what's typed before the cursor is in the range, blanks and all
+            filter_text: Some(match item.snippet {
+                true => item.label.clone(),
+                false => item.text.clone(),
+            }),
+            sort_text: Some(format!("{i:06}")),
+            insert_text_format: Some(match item.snippet {
+                true => InsertTextFormat::SNIPPET,
+                false => InsertTextFormat::PLAIN_TEXT,
+            }),
+            text_edit: Some(CompletionTextEdit::Edit(TextEdit::new(range(lines, item.range), item.text))),
+            ..CompletionItem::new_simple(item.label, String::new())
+        });
+        Ok(Some(CompletionResponse::Array(items.collect())))
     }
 
     async fn hover(&self, params: HoverParams) -> Result<Option<Hover>> {
-        let range = params.text_document_position_params;
+        let TextDocumentPositionParams { text_document, position } = params.text_document_position_params;
+        let mut db = self.db();
+        let source = db.input(&path(&text_document.uri));
+        let lines = parse(&*db, source).lines(&*db);
+        let Ok(offset) = offset(lines, position) else {return Ok(None)};
+        let Some((rng, value)) = hover(&*db, db.main_of(source), source, offset) else {return Ok(None)};
         Ok(Some(Hover {
             contents: HoverContents::Markup(MarkupContent {
                 kind: MarkupKind::Markdown,
-                value: "正在施工🚧".to_string(),
+                value,
             }),
-            range: Some(Range::new(range.position, range.position)),
+            range: Some(range(lines, rng)),
         }))
     }
 
+    async fn goto_definition(&self, params: GotoDefinitionParams) -> Result<Option<GotoDefinitionResponse>> {
+        let TextDocumentPositionParams { text_document, position } = params.text_document_position_params;
+        let mut db = self.db();
+        let source = db.input(&path(&text_document.uri));
+        let Ok(offset) = offset(parse(&*db, source).lines(&*db), position) else {return Ok(None)};
+        let found = nav::definition(&*db, db.main_of(source), source, offset);
+        let locations: Vec<_> = found.into_iter().filter_map(|(f, rng)| location(&*db, f, rng)).collect();
+        match locations.is_empty() {
+            true => Ok(None),
+            false => Ok(Some(GotoDefinitionResponse::Array(locations))),
+        }
+    }
+
+    async fn references(&self, params: ReferenceParams) -> Result<Option<Vec<Location>>> {
+        let TextDocumentPositionParams { text_document, position } = params.text_document_position;
+        let declaration = params.context.include_declaration;
+        let mut db = self.db();
+        let source = db.input(&path(&text_document.uri));
+        let Ok(offset) = offset(parse(&*db, source).lines(&*db), position) else {return Ok(None)};
+        let found = nav::references(&*db, db.main_of(source), source, offset);
+        let found = found.into_iter().filter(|(_, _, def)| declaration || !def);
+        Ok(Some(found.filter_map(|(f, rng, _)| location(&*db, f, rng)).collect()))
+    }
+
+    async fn document_highlight(&self, params: DocumentHighlightParams) -> Result<Option<Vec<DocumentHighlight>>> {
+        let TextDocumentPositionParams { text_document, position } = params.text_document_position_params;
+        let mut db = self.db();
+        let source = db.input(&path(&text_document.uri));
+        let lines = parse(&*db, source).lines(&*db);
+        let Ok(offset) = offset(lines, position) else {return Ok(None)};
+        let found = nav::references(&*db, db.main_of(source), source, offset);
+        let highlights = found.into_iter().filter(|(f, _, _)| *f == source).map(|(_, rng, def)| DocumentHighlight {
+            range: range(lines, rng),
+            kind: Some(match def {
+                true => DocumentHighlightKind::WRITE,
+                false => DocumentHighlightKind::READ,
+            }),
+        });
+        Ok(Some(highlights.collect()))
+    }
+
+    async fn prepare_rename(&self, params: TextDocumentPositionParams) -> Result<Option<PrepareRenameResponse>> {
+        let TextDocumentPositionParams { text_document, position } = params;
+        let mut db = self.db();
+        let source = db.input(&path(&text_document.uri));
+        let lines = parse(&*db, source).lines(&*db);
+        let Ok(offset) = offset(lines, position) else {return Ok(None)};
+        let found = nav::renamable(&*db, db.main_of(source), source, offset);
+        Ok(found.map(|rng| PrepareRenameResponse::Range(range(lines, rng))))
+    }
+
+    async fn rename(&self, params: RenameParams) -> Result<Option<WorkspaceEdit>> {
+        let TextDocumentPositionParams { text_document, position } = params.text_document_position;
+        let mut db = self.db();
+        let source = db.input(&path(&text_document.uri));
+        let Ok(offset) = offset(parse(&*db, source).lines(&*db), position) else {return Ok(None)};
+        let edits = nav::rename(&*db, db.main_of(source), source, offset, &params.new_name);
+        let edits = edits.map_err(Error::invalid_params)?;
+        let mut changes: HashMap<Url, Vec<TextEdit>> = HashMap::new();
+        for (file, rng, new_text) in edits {
+            let Some(Location { uri, range }) = location(&*db, file, rng) else {continue};
+            changes.entry(uri).or_default().push(TextEdit { range, new_text });
+        }
+        Ok(Some(WorkspaceEdit::new(changes)))
+    }
+
+    async fn document_symbol(&self, params: DocumentSymbolParams) -> Result<Option<DocumentSymbolResponse>> {
+        let mut db = self.db();
+        let source = db.input(&path(&params.text_document.uri));
+        Ok(Some(DocumentSymbolResponse::Nested(outline(&*db, source))))
+    }
+
     async fn execute_command(&self, params: ExecuteCommandParams) -> Result<Option<Value>> {
         if params.command == "custom.notification" {
             self.client
@@ -158,15 +371,19 @@ impl LanguageServer for GlobalState {
 
 #[tokio::main]
 async fn main() {
-    #[cfg(feature = "runtime-agnostic")]
-    use tokio_util::compat::{TokioAsyncReadCompatExt, TokioAsyncWriteCompatExt};
-
     tracing_subscriber::fmt().init();
 
     let (stdin, stdout) = (tokio::io::stdin(), tokio::io::stdout());
-    #[cfg(feature = "runtime-agnostic")]
-    let (stdin, stdout) = (stdin.compat(), stdout.compat_write());
 
-    let (service, socket) = LspService::new(|client| GlobalState::new(client));
+    // dbk --keywords DIR --keywords DIR
+    let mut keyword_dirs = vec![];
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--keywords" {
+            keyword_dirs.extend(args.next().map(PathBuf::from));
+        }
+    }
+
+    let (service, socket) = LspService::new(|client| GlobalState::new(client, keyword_dirs));
     Server::new(stdin, stdout, socket).serve(service).await;
 }
@@ -0,0 +1,288 @@
+//! records of a card against its keyword schema
+
+use std::ops::Range;
+
+use syntax::{
+    ast::AstNode,
+    dyna_nodes::{Card, Record},
+    parse::{Format, TextRange, TextSize},
+};
+
+use crate::schema::{CardSchema, FieldSchema, Kind, KeywordSchema};
+
+/// bad values, missing cards and cards too many
+pub fn check_card(kw: &KeywordSchema, card: &Card, format: Format) -> Vec<(TextRange, String)> {
+    let mut errs = vec![];
+    let at = card
+        .keyword()
+        .map_or(card.syntax().text_range(), |k| k.syntax().text_range());
+    let (missing, extra) = walk(kw, card, format, |schema, record, _| {
+        for (field, range, text) in slots(schema, record, format) {
+            if let Err(msg) = field.check(&text) {
+                errs.push((range, msg));
+            }
+        }
+    });
+    if let Some(schema) = missing {
+        let names: Vec<_> = schema.fields.iter().map(|f| f.name.as_str()).collect();
+        errs.push((at, format!("missing card `{}`", names.join(" "))));
+    }
+    for record in extra {
+        let msg = format!("*{} takes no more cards", kw.name);
+        errs.push((record.line_range(), msg));
+    }
+    errs
+}
+
+/// the records of `card` as the schema reads them. `read` gets each with
+/// its schema card and what the fields of its round read so far, its own
+/// too unless the card repeats. gives back the first card that has to be
+/// there but isn't, and the records left over
+pub fn walk<'a>(
+    kw: &'a KeywordSchema,
+    card: &Card,
+    format: Format,
+    mut read: impl FnMut(&'a CardSchema, &Record, &[(String, String)]),
+) -> (Option<&'a CardSchema>, Vec<Record>) {
+    let mut records = card.records().peekable();
+    let mut missing = None;
+    'rounds: loop {
+        // `(field, value)` read so far, for the conditions
+        let mut values: Vec<(String, String)> = vec![];
+        for schema in &kw.cards {
+            if let Some(cond) = &schema.when {
+                if !cond.holds(value(&values, &cond.field)) {
+                    continue;
+                }
+            }
+            if schema.repeat {
+                for record in records.by_ref() {
+                    read(schema, &record, &values);
+                }
+                break 'rounds;
+            }
+            match records.next() {
+                Some(record) => {
+                    values.extend(values_of(schema, &record, format));
+                    read(schema, &record, &values);
+                }
+                None => {
+                    if !schema.is_optional() {
+                        missing = Some(schema);
+                    }
+                    break 'rounds;
+                }
+            }
+        }
+        if !kw.repeat || records.peek().is_none() {
+            break;
+        }
+    }
+    (missing, records.collect())
+}
+
+/// a field of a record, as `field_at` finds it
+pub struct FieldAt<'a> {
+    pub card: &'a CardSchema,
+    pub field: &'a FieldSchema,
+    /// of the slot, blanks and all
+    pub range: TextRange,
+    pub text: String,
+    /// what the fields of the round read so far
+    pub values: Vec<(String, String)>,
+    /// of a comma separated record
+    pub free: bool,
+}
+
+/// the field of `card` that has `offset`
+pub fn field_at<'a>(kw: &'a KeywordSchema, card: &Card, format: Format, offset: TextSize) -> Option<FieldAt<'a>> {
+    let mut found = None;
+    walk(kw, card, format, |schema, record, values| {
+        if found.is_some() || !record.line_range().contains_inclusive(offset) {
+            return;
+        }
+        // the end of the line is still the last field, it's where one types
+        let end = record.line_range().end();
+        let mut fields = slots(schema, record, format).into_iter();
+        let at = |range: &TextRange| range.contains(offset) || range.end() == offset && offset == end;
+        let Some((field, range, text)) = fields.find(|(_, range, _)| at(range)) else {return};
+        let (values, free) = (values.to_vec(), record.is_free_format());
+        found = Some(FieldAt { card: schema, field, range, text, values, free });
+    });
+    found
+}
+
+/// the last `field` read, blank if there's none
+pub fn value<'v>(values: &'v [(String, String)], field: &str) -> &'v str {
+    values
+        .iter()
+        .rev()
+        .find(|(name, _)| name == field)
+        .map_or("", |(_, v)| v.as_str())
+}
+
+/// what each field of one record reads, defaults filled in
+fn values_of(schema: &CardSchema, record: &Record, format: Format) -> Vec<(String, String)> {
+    let mut values = vec![];
+    for (field, _, text) in slots(schema, record, format) {
+        let value = match text.trim() {
+            "" => match (&field.default, field.kind) {
+                (Some(d), _) => d.clone(),
+                (None, Some(Kind::Text)) => String::new(),
+                (None, _) => "0".to_string(),
+            },
+            v => v.to_string(),
+        };
+        values.push((field.name.clone(), value));
+    }
+    values
+}
+
+/// each field of the schema with where it is on the line and its text
+pub fn slots<'a>(
+    schema: &'a CardSchema,
+    record: &Record,
+    format: Format,
+) -> Vec<(&'a FieldSchema, TextRange, String)> {
+    let start = record.syntax().text_range().start();
+    let text = record.syntax().text().to_string();
+    let mut slots = vec![];
+    columns(schema, record, format, |field, range| {
+        // a column ending in the middle of a character has no text
+        if let Some(text) = text.get(Range::<usize>::from(range - start)) {
+            slots.push((field, range, text.to_string()));
+        }
+    });
+    slots
+}
+
+/// where each field of the schema is on the line, [`slots`] without the
+/// text, for the mesh with a record on every line
+pub fn columns<'a>(
+    schema: &'a CardSchema,
+    record: &Record,
+    format: Format,
+    mut at: impl FnMut(&'a FieldSchema, TextRange),
+) {
+    if record.is_free_format() {
+        for (field, node) in schema.fields.iter().zip(record.fields()) {
+            at(field, node.syntax().text_range());
+        }
+        return;
+    }
+    let line = record.line_range();
+    let len = usize::from(line.len());
+    let mut shift = 0;
+    for field in &schema.fields {
+        let width = width(field, format);
+        let a = (field.start + shift).min(len);
+        let b = (a + width).min(len);
+        shift += width - field.width;
+        let range = TextRange::new(TextSize::from(a as u32), TextSize::from(b as u32));
+        at(field, range + line.start());
+    }
+}
+
+/// long makes every field 20 wide, i10 the 8 column ids 10
+pub fn width(field: &FieldSchema, format: Format) -> usize {
+    match (format, field.kind) {
+        (_, Some(Kind::Text)) => field.width,
+        (f, _) if f.is_long() => field.width.max(20),
+        (Format::I10, _) if field.width == 8 => 10,
+        _ => field.width,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use syntax::{
+        dyna_nodes::Card,
+        parse::{parse_text, Format, TextSize},
+    };
+
+    use super::{check_card, field_at, value};
+    use crate::{
+        schema::KeywordSchema,
+        template::{apply_spec, from_template},
+    };
+
+    const SHELL: &str = "*SECTION_SHELL
+$#   secid    elform     icomp
+         ?         1         0
+$#      t1        t2
+         ?
+$#      b1        b2
+         ?
+*DEFINE_CURVE
+$#    lcid
+         ?
+$#              a1                  o1
+                 0.0                 0.0
+";
+
+    fn schema(spec: &str) -> Vec<KeywordSchema> {
+        let mut keywords = from_template(SHELL);
+        let spec = format!("*SECTION_SHELL{spec}\nsecid I (0,)\nicomp I\n    0 no\n    1 yes\ncard 3 if icomp=1\n*DEFINE_CURVE\ncard 2 repeat\n");
+        apply_spec(&mut keywords, &spec).unwrap();
+        keywords
+    }
+
+    fn card(text: &str) -> Card {
+        parse_text(text).tree().cards().next().unwrap()
+    }
+
+    fn errors(kw: &KeywordSchema, text: &str) -> Vec<String> {
+        check_card(kw, &card(text), Format::Standard).into_iter().map(|(_, msg)| msg).collect()
+    }
+
+    #[test]
+    fn conditional_cards() {
+        let keywords = schema("");
+        let shell = &keywords[0];
+        assert!(errors(shell, "*SECTION_SHELL\n         1         2         0\n       1.0\n").is_empty());
+        let text = "*SECTION_SHELL\n         1         2         1\n       1.0\n";
+        assert_eq!(errors(shell, text), vec!["missing card `b1 b2`"]);
+        assert!(errors(shell, &format!("{text}       45.\n")).is_empty());
+        // free format reads the same
+        assert!(errors(shell, "*SECTION_SHELL\n1,2,1\n1.0\n45.\n").is_empty());
+        let text = "*SECTION_SHELL\n         1         2         0\n       1.0\n       45.\n";
+        assert_eq!(errors(shell, text), vec!["*SECTION_SHELL takes no more cards"]);
+    }
+
+    #[test]
+    fn bad_values_where_they_are() {
+        let keywords = schema("");
+        let text = "*SECTION_SHELL\n         0         2         3\n       1.0\n";
+        let errs = check_card(&keywords[0], &card(text), Format::Standard);
+        let found: Vec<_> = errs.iter().map(|(r, msg)| (&text[*r], msg.as_str())).collect();
+        assert_eq!(found, vec![("         0", "`0` is out of range (0,)"), ("         3", "`3` is not one of 0, 1")]);
+    }
+
+    #[test]
+    fn repeating_keywords_and_cards() {
+        let keywords = schema("  repeat");
+        let round = "         1         2         0\n       1.0\n";
+        assert!(errors(&keywords[0], &format!("*SECTION_SHELL\n{round}{round}")).is_empty());
+        let text = format!("*SECTION_SHELL\n{round}         2\n");
+        assert_eq!(errors(&keywords[0], &text), vec!["missing card `t1 t2`"]);
+        let points = "                 0.0                 0.0\n".repeat(5);
+        assert!(errors(&keywords[1], &format!("*DEFINE_CURVE\n         1\n{points}")).is_empty());
+        assert_eq!(errors(&keywords[1], "*DEFINE_CURVE\n"), vec!["missing card `lcid`"]);
+    }
+
+    #[test]
+    fn the_field_at_an_offset() {
+        let keywords = schema("");
+        let text = "*SECTION_SHELL\n         1         2         1\n       1.0\n";
+        let card = card(text);
+        let at = |i: usize| field_at(&keywords[0], &card, Format::Standard, TextSize::from(i as u32));
+        let found = at(text.find('2').unwrap()).unwrap();
+        assert_eq!((found.field.name.as_str(), found.text.as_str()), ("elform", "         2"));
+        assert_eq!(value(&found.values, "secid"), "1");
+        // the end of the line is the last field
+        let found = at(text.find("1.0").unwrap() + 3).unwrap();
+        assert_eq!(found.field.name, "t1");
+        assert_eq!(value(&found.values, "icomp"), "1");
+        assert!(at(3).is_none());
+    }
+}
@@ -0,0 +1,273 @@
+//! what may be written where the cursor is
+
+use syntax::{
+    ast::AstNode,
+    parse::{Format, TextRange, TextSize},
+};
+
+use crate::{
+    check::{field_at, value, width, FieldAt},
+    include::model,
+    index::{id_kind, index},
+    ir::{parse, Source},
+    schema::{Entity, Kind, KeywordSchema},
+};
+
+/// one thing to offer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Item {
+    pub label: String,
+    pub detail: Option<String>,
+    /// what goes in place of `range`
+    pub text: String,
+    pub range: TextRange,
+    /// `text` has tab stops
+    pub snippet: bool,
+}
+
+/// keywords after a `*`, else what the field at `offset` takes, in the model of `main`
+pub fn complete(db: &dyn crate::Db, main: Source, source: Source, offset: TextSize) -> Vec<Item> {
+    let parse = parse(db, source).node(db);
+    let text = parse.syntax_node().text().to_string();
+    let at = usize::from(offset).min(text.len());
+    let start = text[..at].rfind('\n').map_or(0, |i| i + 1);
+    let line = &text[start..];
+    let line = &line[..line.find(['\r', '\n']).unwrap_or(line.len())];
+    // `*SEC`, the whole name goes
+    if let (Some(name), true) = (line.strip_prefix('*'), at > start) {
+        let typed = name.get(..at - start - 1).unwrap_or(name);
+        if typed.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            let len = name.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(name.len());
+            let range = TextRange::at(TextSize::from(start as u32), TextSize::from(len as u32 + 1));
+            return keywords(db, typed, parse.tree().format(), range);
+        }
+    }
+    values(db, main, source, offset).unwrap_or_default()
+}
+
+/// keywords starting with `typed`, each with its template
+fn keywords(db: &dyn crate::Db, typed: &str, format: Format, range: TextRange) -> Vec<Item> {
+    let typed = typed.to_ascii_uppercase();
+    let schema = db.schema().schema(db);
+    let keywords = schema.keywords().iter().filter(|k| k.name.starts_with(&typed));
+    keywords
+        .map(|kw| Item {
+            label: format!("*{}", kw.name),
+            detail: kw.summary.clone(),
+            text: snippet(kw, format),
+            range,
+            snippet: true,
+        })
+        .collect()
+}
+
+/// IDs of what the field names, or its choices. each right-aligned in its slot
+fn values(db: &dyn crate::Db, main: Source, source: Source, offset: TextSize) -> Option<Vec<Item>> {
+    let file = parse(db, source).node(db).tree();
+    let card = file.cards().find(|c| c.syntax().text_range().contains_inclusive(offset))?;
+    let schema = db.schema().schema(db).get(&card.keyword()?.name())?;
+    let format = card.format(file.format());
+    let FieldAt { field, range, values, free, .. } = field_at(&schema, &card, format, offset)?;
+    // the whole width, the line may end before the slot does
+    let width = width(field, format);
+    let item = |label: String, detail: Option<String>| Item {
+        text: match free {
+            true => label.clone(),
+            false => format!("{label:>width$}"),
+        },
+        label,
+        detail,
+        range,
+        snippet: false,
+    };
+    if !field.choices.is_empty() {
+        let choices = field.choices.iter().map(|c| item(c.value.clone(), Some(c.meaning.clone())));
+        return Some(choices.collect());
+    }
+    let entity = field.refers.iter().find_map(|(entity, when)| {
+        let holds = when.as_ref().map_or(true, |c| c.holds(value(&values, &c.field)));
+        holds.then_some(*entity)
+    })?;
+    // too many to pick from
+    if matches!(entity, Entity::Node | Entity::Element) {
+        return None;
+    }
+    // IDs as written here, the include's offset taken off
+    let model = model(db, main);
+    let entry = model.deck.iter().find(|e| e.file == source && e.range.contains_inclusive(offset));
+    let shift = entry.map_or(0, |e| model.transform(e).offset(id_kind(entity)));
+    let index = index(db, main);
+    let mut items: Vec<(i64, Item)> = vec![];
+    for d in index.definitions.iter().filter(|d| d.entity == entity) {
+        let id = d.id - shift;
+        if id <= 0 || items.iter().any(|(i, _)| *i == id) {
+            continue;
+        }
+        let detail = match &d.title {
+            Some(title) => format!("{title}, *{}", d.keyword),
+            None => format!("*{}", d.keyword),
+        };
+        items.push((id, item(id.to_string(), Some(detail))));
+    }
+    items.sort_by_key(|(id, _)| *id);
+    Some(items.into_iter().map(|(_, item)| item).collect())
+}
+
+/// the keyword and its cards, headers over them. every field is a tab stop,
+/// defaults filled in. cards there only if a field says so are left out
+pub fn snippet(kw: &KeywordSchema, format: Format) -> String {
+    let mut text = format!("*{}\n", kw.name);
+    let mut stop = 0;
+    for card in kw.cards.iter().filter(|c| c.when.is_none()) {
+        let (mut header, mut line) = (String::new(), String::new());
+        // of `line` as it goes in the deck, tab stops taken out
+        let mut column = 0;
+        let mut shift = 0;
+        for field in &card.fields {
+            let width = width(field, format);
+            let start = field.start + shift;
+            shift += width - field.width;
+            let pad = start.saturating_sub(header.chars().count());
+            header.push_str(&" ".repeat(pad));
+            line.push_str(&" ".repeat(start.saturating_sub(column)));
+            column = column.max(start);
+            stop += 1;
+            if field.kind == Some(Kind::Text) {
+                header.push_str(&field.name);
+                line.push_str(&format!("${{{stop}:{}}}", escape(&field.name)));
+                column += field.name.len();
+                continue;
+            }
+            header.push_str(&format!("{:>width$}", field.name));
+            // one character typed keeps the columns
+            let value = field.default.clone().unwrap_or_default();
+            let pad = width.saturating_sub(value.len().max(1));
+            line.push_str(&" ".repeat(pad));
+            line.push_str(&format!("${{{stop}:{}}}", escape(&value)));
+            column += pad + value.len().max(1);
+        }
+        let header = match header.strip_prefix("  ") {
+            Some(rest) => format!("$#{rest}"),
+            None => format!("$# {header}"),
+        };
+        text.push_str(&format!("{}\n{line}\n", escape(&header)));
+    }
+    text.push_str("$0");
+    text
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('$', "\\$").replace('}', "\\}")
+}
+
+#[cfg(test)]
+mod tests {
+    use syntax::parse::{Format, TextSize};
+
+    use super::{complete, snippet, Item};
+    use crate::{
+        template::{apply_spec, from_template},
+        RootDatabase,
+    };
+
+    /// what's offered `at` past the start of `what` in `path`, the first file
+    /// is the main deck
+    fn complete_at(files: &[(&str, String)], path: &str, what: &str, at: usize) -> Vec<Item> {
+        let mut db = RootDatabase::new();
+        for (path, text) in files {
+            db.open(path, text.clone());
+        }
+        let main = db.input(files[0].0);
+        let source = db.input(path);
+        let text = source.text(&db).clone().unwrap();
+        let offset = TextSize::from((text.find(what).unwrap() + at) as u32);
+        complete(&db, main, source, offset)
+    }
+
+    /// right-aligned in 10 columns each
+    fn line(values: &[&str]) -> String {
+        values.iter().map(|v| format!("{v:>10}")).collect::<String>() + "\n"
+    }
+
+    fn labels(items: &[Item]) -> Vec<&str> {
+        items.iter().map(|i| i.label.as_str()).collect()
+    }
+
+    #[test]
+    fn keywords_after_a_star() {
+        let text = "*SECTION_SH\n".to_string();
+        let items = complete_at(&[("/dbk/main.k", text.clone())], "/dbk/main.k", "*SEC", 4);
+        let labels: Vec<_> = items.iter().map(|i| i.label.as_str()).collect();
+        assert!(labels.contains(&"*SECTION_SHELL_TITLE") && labels.contains(&"*SECTION_SOLID_TITLE"));
+        assert!(labels.iter().all(|l| l.starts_with("*SEC")));
+        // the whole name goes, the template comes in
+        let shell = items.iter().find(|i| i.label == "*SECTION_SHELL_TITLE").unwrap();
+        assert_eq!(&text[shell.range], "*SECTION_SH");
+        assert!(shell.snippet && shell.text.starts_with("*SECTION_SHELL_TITLE\n\\$# title\n${1:title}\n"));
+        assert_eq!(shell.detail.as_deref(), Some("shell element formulation and thickness"));
+        // case doesn't matter, nothing past what's typed
+        let items = complete_at(&[("/dbk/main.k", "*mat_el\n".to_string())], "/dbk/main.k", "*mat_el", 7);
+        assert!(items.iter().any(|i| i.label == "*MAT_ELASTIC_TITLE"));
+        let items = complete_at(&[("/dbk/main.k", "*nothing_like_it\n".to_string())], "/dbk/main.k", "*", 5);
+        assert!(items.is_empty());
+    }
+
+    #[test]
+    fn templates_as_snippets() {
+        let text = "*SECTION_SHELL\n$#   secid    elform      shrf\n         ?         2     0.833\n$#      t1\n         ?\n";
+        let mut keywords = from_template(text);
+        apply_spec(&mut keywords, "*SECTION_SHELL\ncard 2 if elform=3\n").unwrap();
+        let expected = "*SECTION_SHELL\n\\$#   secid    elform      shrf\n         ${1:}         ${2:2}     ${3:0.833}\n$0";
+        assert_eq!(snippet(&keywords[0], Format::Standard), expected);
+        // long columns, the values keep to the right
+        let long = snippet(&keywords[0], Format::Long);
+        let line = format!("{0}${{1:}}{0}${{2:2}}{1}${{3:0.833}}", " ".repeat(19), " ".repeat(15));
+        assert_eq!(long.lines().nth(2), Some(line.as_str()));
+        let header = format!("\\$#{}secid{}elform{}shrf", " ".repeat(13), " ".repeat(14), " ".repeat(16));
+        assert_eq!(long.lines().nth(1), Some(header.as_str()));
+    }
+
+    #[test]
+    fn ids_of_what_the_field_names() {
+        let mat = |id: &str| line(&[id, "7.8e-9", "210000.", "0.3"]);
+        let main = format!(
+            "*MAT_ELASTIC_TITLE\nsteel\n{}*MAT_ELASTIC\n{}*INCLUDE_TRANSFORM\n/dbk/inc.k\n{}*PART\nbumper\n{}",
+            mat("5"),
+            mat("6"),
+            line(&["0", "0", "0", "100"]),
+            line(&["1", "1", "5"]),
+        );
+        let inc = format!("*MAT_ELASTIC\n{}*PART\nbeam\n{}", mat("5"), line(&["2", "1", "5"]));
+        let files = [("/dbk/main.k", main.clone()), ("/dbk/inc.k", inc.clone())];
+        let items = complete_at(&files, "/dbk/main.k", "bumper\n", 7 + 25);
+        assert_eq!(labels(&items), vec!["5", "6", "105"]);
+        assert_eq!(items[0].detail.as_deref(), Some("steel, *MAT_ELASTIC_TITLE"));
+        assert_eq!(items[1].detail.as_deref(), Some("*MAT_ELASTIC"));
+        // the whole slot, right-aligned in it
+        assert!(items.iter().all(|i| &main[i.range] == "         5" && !i.snippet));
+        assert_eq!(items[2].text, "       105");
+        // as written in the include, its offset taken off
+        let items = complete_at(&files, "/dbk/inc.k", "beam\n", 5 + 25);
+        assert_eq!(labels(&items), vec!["5"]);
+        assert_eq!(&inc[items[0].range], "         5");
+    }
+
+    #[test]
+    fn choices_curves_and_free_format() {
+        let text = format!("*SECTION_SHELL\n{}", line(&["1", "2"]));
+        let items = complete_at(&[("/dbk/main.k", text)], "/dbk/main.k", "         2", 5);
+        let elform = items.iter().find(|i| i.label == "2").unwrap();
+        assert_eq!(elform.detail.as_deref(), Some("Belytschko-Tsay"));
+        assert_eq!(elform.text, "         2");
+        assert_eq!(items[0].label, "-16");
+        let text = format!("*DEFINE_CURVE\n{}*CONTROL_TIMESTEP\n{}", line(&["3"]), line(&["0.0", "0.9", "0", "0.0", "0.0", "4"]));
+        assert_eq!(labels(&complete_at(&[("/dbk/main.k", text)], "/dbk/main.k", "         4", 5)), vec!["3"]);
+        // nodes are too many
+        let text = format!("*NODE\n{:>8}\n*ELEMENT_SHELL\n{:>8}{:>8}{:>8}\n", 1, 10, 1, 1);
+        assert!(complete_at(&[("/dbk/main.k", text)], "/dbk/main.k", "*ELEMENT_SHELL\n", 15 + 20).is_empty());
+        let text = format!("*MAT_ELASTIC\n{}*PART\nbumper\n1,1,5\n", line(&["5"]));
+        let items = complete_at(&[("/dbk/main.k", text.clone())], "/dbk/main.k", "1,1,5", 4);
+        assert_eq!(labels(&items), vec!["5"]);
+        assert_eq!((items[0].text.as_str(), &text[items[0].range]), ("5", "5"));
+    }
+}
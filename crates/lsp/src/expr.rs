@@ -0,0 +1,383 @@
+//! the arithmetic of `*PARAMETER_EXPRESSION`, Fortran like the solver reads it:
+//! `+ - * / **`, parentheses and its intrinsic functions, case doesn't matter
+
+use syntax::parse::{TextRange, TextSize};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Number(f64),
+    /// a parameter, `&` or not, where it is in the text
+    Name(String, TextRange),
+    Neg(Box<Expr>),
+    Binary(Op, Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+}
+
+/// the intrinsics and how many arguments they take, `None` is 2 or more
+const FUNCTIONS: &[(&str, Option<usize>)] = &[
+    ("abs", Some(1)),
+    ("sqrt", Some(1)),
+    ("exp", Some(1)),
+    ("log", Some(1)),
+    ("log10", Some(1)),
+    ("sin", Some(1)),
+    ("cos", Some(1)),
+    ("tan", Some(1)),
+    ("asin", Some(1)),
+    ("acos", Some(1)),
+    ("atan", Some(1)),
+    ("atan2", Some(2)),
+    ("sinh", Some(1)),
+    ("cosh", Some(1)),
+    ("tanh", Some(1)),
+    ("int", Some(1)),
+    ("nint", Some(1)),
+    ("aint", Some(1)),
+    ("anint", Some(1)),
+    ("mod", Some(2)),
+    ("sign", Some(2)),
+    ("min", None),
+    ("max", None),
+];
+
+impl Expr {
+    pub fn parse(text: &str) -> Result<Expr, String> {
+        let mut parser = Parser { tokens: tokens(text)?, at: 0 };
+        let expr = parser.sum()?;
+        match parser.tokens.get(parser.at) {
+            None => Ok(expr),
+            Some((t, _)) => Err(format!("unexpected `{t}`")),
+        }
+    }
+
+    /// parameters it reads, in order
+    pub fn names(&self) -> Vec<(&str, TextRange)> {
+        let mut names = vec![];
+        self.walk(&mut |e| {
+            if let Expr::Name(name, range) = e {
+                names.push((name.as_str(), *range));
+            }
+        });
+        names
+    }
+
+    fn walk<'a>(&'a self, f: &mut impl FnMut(&'a Expr)) {
+        f(self);
+        match self {
+            Expr::Number(_) | Expr::Name(..) => {}
+            Expr::Neg(e) => e.walk(f),
+            Expr::Binary(_, a, b) => {
+                a.walk(f);
+                b.walk(f);
+            }
+            Expr::Call(_, args) => args.iter().for_each(|a| a.walk(f)),
+        }
+    }
+
+    /// `lookup` gives the value of a parameter by name as written
+    pub fn eval(&self, lookup: &mut impl FnMut(&str) -> Option<f64>) -> Result<f64, String> {
+        let value = match self {
+            Expr::Number(x) => *x,
+            Expr::Name(name, _) => lookup(name).ok_or_else(|| format!("`{name}` has no number value"))?,
+            Expr::Neg(e) => -e.eval(lookup)?,
+            Expr::Binary(op, a, b) => {
+                let (a, b) = (a.eval(lookup)?, b.eval(lookup)?);
+                match op {
+                    Op::Add => a + b,
+                    Op::Sub => a - b,
+                    Op::Mul => a * b,
+                    Op::Div if b == 0. => return Err("division by zero".to_string()),
+                    Op::Div => a / b,
+                    Op::Pow => a.powf(b),
+                }
+            }
+            Expr::Call(name, args) => {
+                let args = args.iter().map(|a| a.eval(lookup)).collect::<Result<Vec<_>, _>>()?;
+                call(name, &args)?
+            }
+        };
+        match value.is_finite() {
+            true => Ok(value),
+            false => Err("the result is not a finite number".to_string()),
+        }
+    }
+}
+
+fn call(name: &str, args: &[f64]) -> Result<f64, String> {
+    let x = args[0];
+    let y = || args[1];
+    Ok(match name {
+        "abs" => x.abs(),
+        "sqrt" => x.sqrt(),
+        "exp" => x.exp(),
+        "log" => x.ln(),
+        "log10" => x.log10(),
+        "sin" => x.sin(),
+        "cos" => x.cos(),
+        "tan" => x.tan(),
+        "asin" => x.asin(),
+        "acos" => x.acos(),
+        "atan" => x.atan(),
+        "atan2" => x.atan2(y()),
+        "sinh" => x.sinh(),
+        "cosh" => x.cosh(),
+        "tanh" => x.tanh(),
+        "int" | "aint" => x.trunc(),
+        "nint" | "anint" => x.round(),
+        "mod" if y() == 0. => return Err("`mod` by zero".to_string()),
+        // keeps the sign of `x`, like Fortran
+        "mod" => x % y(),
+        "sign" => x.abs().copysign(y()),
+        "min" => args.iter().copied().fold(f64::INFINITY, f64::min),
+        "max" => args.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        _ => return Err(format!("unknown function `{name}`")),
+    })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Name(String),
+    Op(&'static str),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Number(x) => write!(f, "{x}"),
+            Token::Name(n) => f.write_str(n),
+            Token::Op(o) => f.write_str(o),
+        }
+    }
+}
+
+fn tokens(text: &str) -> Result<Vec<(Token, TextRange)>, String> {
+    let bytes = text.as_bytes();
+    let mut tokens = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        let start = i;
+        let c = bytes[i];
+        let token = match c {
+            b' ' | b'\t' => {
+                i += 1;
+                continue;
+            }
+            b'0'..=b'9' | b'.' => {
+                while i < bytes.len() && (bytes[i].is_ascii_digit() || bytes[i] == b'.') {
+                    i += 1;
+                }
+                // `1e3`, `1.d-3`
+                if i < bytes.len() && matches!(bytes[i], b'e' | b'E' | b'd' | b'D') {
+                    let mut j = i + 1;
+                    if j < bytes.len() && matches!(bytes[j], b'+' | b'-') {
+                        j += 1;
+                    }
+                    if j < bytes.len() && bytes[j].is_ascii_digit() {
+                        while j < bytes.len() && bytes[j].is_ascii_digit() {
+                            j += 1;
+                        }
+                        i = j;
+                    }
+                }
+                let number = text[start..i].replace(['d', 'D'], "e");
+                let x: f64 = number.parse().map_err(|_| format!("`{}` is not a number", &text[start..i]))?;
+                if !x.is_finite() {
+                    return Err(format!("`{}` is not a finite number", &text[start..i]));
+                }
+                Token::Number(x)
+            }
+            b'&' | b'a'..=b'z' | b'A'..=b'Z' | b'_' => {
+                i += 1;
+                while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                    i += 1;
+                }
+                let name = text[start..i].trim_start_matches('&');
+                if name.is_empty() {
+                    return Err("`&` without a name".to_string());
+                }
+                Token::Name(name.to_string())
+            }
+            b'*' if bytes.get(i + 1) == Some(&b'*') => {
+                i += 2;
+                Token::Op("**")
+            }
+            _ => {
+                let ops = ["+", "-", "*", "/", "^", "(", ")", ","];
+                let Some(op) = ops.into_iter().find(|o| o.as_bytes()[0] == c) else {
+                    let c = text[i..].chars().next().unwrap_or_default();
+                    return Err(format!("unexpected `{c}`"));
+                };
+                i += 1;
+                Token::Op(op)
+            }
+        };
+        let range = TextRange::new(TextSize::from(start as u32), TextSize::from(i as u32));
+        tokens.push((token, range));
+    }
+    Ok(tokens)
+}
+
+/// `sum := product (+|- product)*`, `product := unary (*|/ unary)*`,
+/// `unary := (+|-) unary | power`, `power := atom (**|^ unary)?`,
+/// so `-a**2` is `-(a**2)` and `2**-1` is fine
+struct Parser {
+    tokens: Vec<(Token, TextRange)>,
+    at: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.at).map(|(t, _)| t)
+    }
+
+    fn eat(&mut self, op: &str) -> bool {
+        let found = matches!(self.peek(), Some(Token::Op(o)) if *o == op);
+        self.at += usize::from(found);
+        found
+    }
+
+    fn sum(&mut self) -> Result<Expr, String> {
+        let mut expr = self.product()?;
+        loop {
+            let op = match () {
+                _ if self.eat("+") => Op::Add,
+                _ if self.eat("-") => Op::Sub,
+                _ => return Ok(expr),
+            };
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.product()?));
+        }
+    }
+
+    fn product(&mut self) -> Result<Expr, String> {
+        let mut expr = self.unary()?;
+        loop {
+            let op = match () {
+                _ if self.eat("*") => Op::Mul,
+                _ if self.eat("/") => Op::Div,
+                _ => return Ok(expr),
+            };
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.eat("-") {
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        if self.eat("+") {
+            return self.unary();
+        }
+        let base = self.atom()?;
+        match self.eat("**") || self.eat("^") {
+            true => Ok(Expr::Binary(Op::Pow, Box::new(base), Box::new(self.unary()?))),
+            false => Ok(base),
+        }
+    }
+
+    fn atom(&mut self) -> Result<Expr, String> {
+        let Some((token, range)) = self.tokens.get(self.at).cloned() else {
+            return Err("the expression ends too early".to_string());
+        };
+        self.at += 1;
+        match token {
+            Token::Number(x) => Ok(Expr::Number(x)),
+            Token::Op("(") => {
+                let expr = self.sum()?;
+                match self.eat(")") {
+                    true => Ok(expr),
+                    false => Err("`(` is not closed".to_string()),
+                }
+            }
+            Token::Name(name) if self.eat("(") => {
+                let lower = name.to_ascii_lowercase();
+                let Some(&(_, arity)) = FUNCTIONS.iter().find(|(f, _)| *f == lower) else {
+                    return Err(format!("unknown function `{name}`"));
+                };
+                let mut args = vec![self.sum()?];
+                while self.eat(",") {
+                    args.push(self.sum()?);
+                }
+                if !self.eat(")") {
+                    return Err(format!("`{name}(` is not closed"));
+                }
+                match arity {
+                    Some(n) if n != args.len() => Err(format!("`{lower}` takes {n} argument(s)")),
+                    None if args.len() < 2 => Err(format!("`{lower}` takes 2 or more arguments")),
+                    _ => Ok(Expr::Call(lower, args)),
+                }
+            }
+            Token::Name(name) => Ok(Expr::Name(name, range)),
+            Token::Op(op) => Err(format!("unexpected `{op}`")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Expr;
+
+    fn eval(text: &str) -> Result<f64, String> {
+        let mut values = |name: &str| match name {
+            "a" => Some(5.),
+            "B" => Some(-2.),
+            _ => None,
+        };
+        Expr::parse(text)?.eval(&mut values)
+    }
+
+    #[test]
+    fn precedence() {
+        assert_eq!(eval("1+2*3"), Ok(7.));
+        assert_eq!(eval("(1+2)*3"), Ok(9.));
+        assert_eq!(eval("-2**2"), Ok(-4.));
+        assert_eq!(eval("2**-1"), Ok(0.5));
+        assert_eq!(eval("2^3^2"), Ok(512.));
+        assert_eq!(eval("8/2/2"), Ok(2.));
+        assert_eq!(eval("1.d-3*1E3 - +1"), Ok(0.));
+    }
+
+    #[test]
+    fn names_and_functions() {
+        assert_eq!(eval("&a*B"), Ok(-10.));
+        assert_eq!(eval("max(1, &a, 3)"), Ok(5.));
+        assert_eq!(eval("SQRT(16)+abs(B)"), Ok(6.));
+        assert_eq!(eval("mod(-7, 3)"), Ok(-1.));
+        assert_eq!(eval("sign(2, B)"), Ok(-2.));
+        assert_eq!(eval("nint(2.5)+int(-2.5)"), Ok(1.));
+        let expr = Expr::parse("&a + b*2").unwrap();
+        let names: Vec<_> = expr.names().into_iter().map(|(n, r)| (n, usize::from(r.start()), usize::from(r.end()))).collect();
+        assert_eq!(names, vec![("a", 0, 2), ("b", 5, 6)]);
+    }
+
+    #[test]
+    fn errors() {
+        let err = |text: &str| eval(text).unwrap_err();
+        assert_eq!(err("1/0"), "division by zero");
+        assert_eq!(err("mod(1, 0)"), "`mod` by zero");
+        assert_eq!(err("sqrt(-1)"), "the result is not a finite number");
+        assert_eq!(err("0/0"), "division by zero");
+        assert_eq!(err("1d999"), "`1d999` is not a finite number");
+        assert_eq!(err("10**400"), "the result is not a finite number");
+        assert_eq!(err("c+1"), "`c` has no number value");
+        assert_eq!(err("foo(1)"), "unknown function `foo`");
+        assert_eq!(err("atan2(1)"), "`atan2` takes 2 argument(s)");
+        assert_eq!(err("min(1)"), "`min` takes 2 or more arguments");
+        assert_eq!(err("(1"), "`(` is not closed");
+        assert_eq!(err("max(1,2"), "`max(` is not closed");
+        assert_eq!(err("1 2"), "unexpected `2`");
+        assert_eq!(err("1 # 2"), "unexpected `#`");
+        assert_eq!(err("&"), "`&` without a name");
+        assert_eq!(err(""), "the expression ends too early");
+        assert_eq!(err("1é"), "unexpected `é`");
+    }
+}
@@ -1,3 +1,8 @@
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
 use crate::line_index::LineCol;
 use anyhow::format_err;
 use anyhow::Result;
@@ -8,8 +13,9 @@ use text_edit::Indel;
 use text_edit::TextEdit;
 
 use tower_lsp::lsp_types::TextDocumentContentChangeEvent;
-use tower_lsp::lsp_types::{Position, Range};
+use tower_lsp::lsp_types::{Location, Position, Range, Url};
 
+use crate::ir::{parse, Source};
 use crate::line_index;
 
 pub type LspEdit = tower_lsp::lsp_types::TextEdit;
@@ -59,7 +65,52 @@ pub fn text_range(line_index: &LineIndex, range: Range) -> Result<TextRange> {
     let start = offset(line_index, range.start)?;
     let end = offset(line_index, range.end)?;
     match end < start {
-        true => Err(format_err!("Invalid Range").into()),
+        true => Err(format_err!("Invalid Range")),
         false => Ok(TextRange::new(start, end)),
     }
 }
+
+/// the path a file is known by, see [`key`]
+pub fn path(uri: &Url) -> String {
+    match uri.to_file_path() {
+        Ok(path) => key(&path),
+        Err(()) => uri.path().to_string(),
+    }
+}
+
+/// one name a file, however it's got to: canonical if it's there, else its
+/// dir is, so it keeps the name once it's made
+pub fn key(path: &Path) -> String {
+    let path = match (fs::canonicalize(path), path.parent(), path.file_name()) {
+        (Ok(path), _, _) => path,
+        (Err(_), Some(dir), Some(name)) => fs::canonicalize(dir).map_or(path.to_path_buf(), |d| d.join(name)),
+        _ => path.to_path_buf(),
+    };
+    path.to_string_lossy().into_owned()
+}
+
+/// `.` and `..` worked out by the names alone, without the disk. a query
+/// can't read it, [`key`] is for the db
+pub fn normal(path: &Path) -> String {
+    let mut normal = PathBuf::new();
+    for part in path.components() {
+        match part {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normal.pop();
+            }
+            part => normal.push(part),
+        }
+    }
+    normal.to_string_lossy().into_owned()
+}
+
+/// back to a uri
+pub fn uri(path: &str) -> Option<Url> {
+    Url::from_file_path(path).ok()
+}
+
+/// where `rng` of `file` is for the editor
+pub fn location(db: &dyn crate::Db, file: Source, rng: TextRange) -> Option<Location> {
+    Some(Location::new(uri(file.path(db))?, range(parse(db, file).lines(db), rng)))
+}
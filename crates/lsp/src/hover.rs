@@ -0,0 +1,238 @@
+//! what the cursor is on, as markdown
+
+use syntax::{
+    ast::AstNode,
+    keyword::KwOption,
+    parse::{TextRange, TextSize},
+};
+
+use crate::{
+    check::{field_at, FieldAt},
+    index::index,
+    ir::{parse, Source},
+    param::{params, Params},
+    schema::{CardSchema, FieldSchema, Kind, KeywordSchema},
+};
+
+/// a field or keyword of a card the schema knows, else a parameter
+pub fn hover(db: &dyn crate::Db, main: Source, source: Source, offset: TextSize) -> Option<(TextRange, String)> {
+    card(db, main, source, offset).or_else(|| param(db, main, source, offset))
+}
+
+/// a `&name` or the name a `*PARAMETER` defines
+fn param(db: &dyn crate::Db, main: Source, source: Source, offset: TextSize) -> Option<(TextRange, String)> {
+    let params = params(db, main);
+    let (range, param) = params.at(source, offset)?;
+    match param {
+        Some(p) => Some((range, param_text(&params, p))),
+        None => Some((range, "not defined".to_string())),
+    }
+}
+
+fn param_text(params: &Params, p: usize) -> String {
+    let param = &params.params[p];
+    let value = param.value.as_ref().map_or("?".to_string(), |v| v.to_string());
+    let mut text = format!("```\n{} {} = {value}\n```", param.kind, param.name);
+    if let Some(expression) = &param.expression {
+        text.push_str(&format!("\n\n`{expression}`"));
+    }
+    if param.scope.is_some() {
+        text.push_str("\n\nlocal to the include");
+    }
+    text
+}
+
+fn card(db: &dyn crate::Db, main: Source, source: Source, offset: TextSize) -> Option<(TextRange, String)> {
+    let file = parse(db, source).node(db).tree();
+    let card = file.cards().find(|c| c.syntax().text_range().contains_inclusive(offset))?;
+    let kw = card.keyword()?;
+    let schema = db.schema().schema(db).get(&kw.name())?;
+    if kw.syntax().text_range().contains_inclusive(offset) {
+        return Some((kw.syntax().text_range(), layout(&schema)));
+    }
+    let FieldAt { card, field, range: slot, text, .. } = field_at(&schema, &card, card.format(file.format()), offset)?;
+    let mut lines = vec![format!("**\\*{}** {}, `{}`", schema.name, card_name(&schema, card), field.name)];
+    lines.extend(about(field, &text));
+    // what the value names or defines
+    let index = index(db, main);
+    if let Some((entity, id, _)) = index.at(source, offset) {
+        let own = index.definitions_of(entity, id).any(|d| d.file == source && slot.contains_range(d.range));
+        let def = index.definitions_of(entity, id).next();
+        lines.push(match (own, def) {
+            (true, _) => format!("defines {entity} {id}"),
+            (false, None) => format!("{entity} {id}, not defined"),
+            (false, Some(d)) => match &d.title {
+                Some(title) => format!("{entity} {id} of `*{}` {title}", d.keyword),
+                None => format!("{entity} {id} of `*{}`", d.keyword),
+            },
+        });
+    }
+    // a `&name` read here, or the name of a `*PARAMETER`
+    let params = params(db, main);
+    let read = params.references.iter().find(|r| r.file == source && slot.contains_range(r.range));
+    let defined = params.params.iter().position(|p| p.file == source && slot.contains_range(p.range));
+    match (read, defined) {
+        (Some(r), _) => lines.push(match r.param {
+            Some(p) => param_text(&params, p),
+            None => "the parameter is not defined".to_string(),
+        }),
+        (None, Some(p)) => lines.push(param_text(&params, p)),
+        (None, None) => {}
+    }
+    let value = text.trim();
+    let lead = TextSize::of(&text[..text.len() - text.trim_start().len()]);
+    let range = match value.is_empty() {
+        true => slot,
+        false => TextRange::at(slot.start() + lead, TextSize::of(value)),
+    };
+    Some((range, lines.join("\n\n")))
+}
+
+/// type, range, unit, default and choices, `text` is what's written
+fn about(field: &FieldSchema, text: &str) -> Vec<String> {
+    let mut lines = vec![];
+    let mut kind = match field.kind {
+        Some(Kind::Integer) => "integer".to_string(),
+        Some(Kind::Float) => "float".to_string(),
+        Some(Kind::Text) => "text".to_string(),
+        None => "?".to_string(),
+    };
+    if let Some(range) = field.range {
+        kind.push_str(&format!(" in {range}"));
+    }
+    if let Some(unit) = &field.unit {
+        kind.push_str(&format!(", {unit}"));
+    }
+    lines.push(kind);
+    match (&field.default, field.required) {
+        (_, true) => lines.push("has to be given".to_string()),
+        (Some(default), false) => lines.push(format!("default `{default}`")),
+        (None, false) => {}
+    }
+    if field.curve {
+        lines.push("a negative value is a curve ID".to_string());
+    }
+    if !field.choices.is_empty() {
+        let chosen = field.choice(text);
+        let choices = field.choices.iter().map(|c| match chosen == Some(c) {
+            true => format!("- **`{}` {}**", c.value, c.meaning),
+            false => format!("- `{}` {}", c.value, c.meaning),
+        });
+        lines.push(choices.collect::<Vec<_>>().join("\n"));
+        if field.open {
+            lines.push("the usual ones, the manual has more".to_string());
+        }
+    }
+    lines
+}
+
+/// summary and cards of a keyword
+fn layout(schema: &KeywordSchema) -> String {
+    let mut lines = vec![format!("**\\*{}**", schema.name)];
+    lines.extend(schema.summary.clone());
+    let cards = schema.cards.iter().map(|card| {
+        let names: Vec<_> = card.fields.iter().map(|f| f.name.as_str()).collect();
+        let mut line = format!("- {} `{}`", card_name(schema, card), names.join(" "));
+        if let Some(when) = &card.when {
+            line.push_str(&format!(" if {}={}", when.field, when.values.join(",")));
+        }
+        if card.repeat {
+            line.push_str(", as many as needed");
+        } else if card.optional {
+            line.push_str(", optional");
+        }
+        line
+    });
+    lines.push(cards.collect::<Vec<_>>().join("\n"));
+    if schema.repeat {
+        lines.push("the cards may be given again for the next one".to_string());
+    }
+    lines.join("\n\n")
+}
+
+/// `card 2` as the manual numbers them, title and id cards don't count
+fn card_name(schema: &KeywordSchema, card: &CardSchema) -> String {
+    let name = schema.keyword_name();
+    let lead = name.has(KwOption::Title) as usize + name.has(KwOption::Id) as usize;
+    let i = schema.cards.iter().position(|c| std::ptr::eq(c, card)).unwrap_or_default();
+    match i.checked_sub(lead) {
+        Some(n) => format!("card {}", n + 1),
+        None if card.is_id() => "id card".to_string(),
+        None => "title card".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use syntax::parse::TextSize;
+
+    use super::hover;
+    use crate::{RootDatabase};
+
+    /// right-aligned in 10 columns each
+    fn line(values: &[&str]) -> String {
+        values.iter().map(|v| format!("{v:>10}")).collect::<String>() + "\n"
+    }
+
+    fn deck() -> String {
+        format!(
+            "*PARAMETER\n{:<10}{:>10}\n*PARAMETER_EXPRESSION\n{:<10}2*thick\n*SECTION_SHELL\n{}{}*MAT_ELASTIC\n{}*PART\nbumper\n{}",
+            "R   thick",
+            "1.5",
+            "R   twice",
+            line(&["1", "2"]),
+            line(&["&twice", "&none"]),
+            line(&["5", "7.8e-9", "210000.", "0.3"]),
+            line(&["1", "1", "5"]),
+        )
+    }
+
+    /// what the hover covers and says, `at` past the start of `what`
+    fn hover_at(what: &str, at: usize) -> Option<(String, String)> {
+        let text = deck();
+        let mut db = RootDatabase::new();
+        db.open("/dbk/main.k", text.clone());
+        let main = db.input("/dbk/main.k");
+        let offset = TextSize::from((text.find(what).unwrap() + at) as u32);
+        let (range, markdown) = hover(&db, main, main, offset)?;
+        Some((text[range].to_string(), markdown))
+    }
+
+    #[test]
+    fn keywords_and_their_cards() {
+        let (covered, text) = hover_at("*SECTION_SHELL", 3).unwrap();
+        assert!(covered.starts_with("*SECTION_SHELL"));
+        let head = "**\\*SECTION_SHELL**\n\nshell element formulation and thickness\n\n- card 1 `secid elform shrf nip propt qr/irid icomp setyp`";
+        assert!(text.starts_with(head), "{text}");
+        assert!(text.ends_with("the cards may be given again for the next one"), "{text}");
+    }
+
+    #[test]
+    fn fields_and_what_they_name() {
+        let shell = "*SECTION_SHELL\n";
+        let (covered, text) = hover_at(shell, shell.len() + 9).unwrap();
+        assert_eq!(covered, "1");
+        assert_eq!(text, "**\\*SECTION_SHELL** card 1, `secid`\n\ninteger in (0,)\n\nhas to be given\n\ndefines section 1");
+        let (covered, text) = hover_at(shell, shell.len() + 19).unwrap();
+        assert_eq!(covered, "2");
+        assert!(text.contains("\n- **`2` Belytschko-Tsay**\n"), "{text}");
+        assert!(text.ends_with("the usual ones, the manual has more"), "{text}");
+        let part = "bumper\n";
+        let (_, text) = hover_at(part, part.len() + 19).unwrap();
+        assert!(text.ends_with("section 1 of `*SECTION_SHELL`"), "{text}");
+        let (_, text) = hover_at(part, part.len() + 29).unwrap();
+        assert!(text.ends_with("material 5 of `*MAT_ELASTIC`"), "{text}");
+    }
+
+    #[test]
+    fn parameters() {
+        let (covered, text) = hover_at("&twice", 2).unwrap();
+        assert_eq!(covered, "&twice");
+        assert!(text.ends_with("```\nR twice = 3.0\n```\n\n`2*thick`"), "{text}");
+        let (_, text) = hover_at("&none", 2).unwrap();
+        assert!(text.ends_with("the parameter is not defined"), "{text}");
+        let (covered, text) = hover_at("thick", 0).unwrap();
+        assert_eq!(covered, "R   thick");
+        assert!(text.ends_with("```\nR thick = 1.5\n```"), "{text}");
+    }
+}
@@ -0,0 +1,421 @@
+//! the model: a master deck and every file it `*INCLUDE`s, read in the
+//! order the solver reads them
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use syntax::{
+    ast::AstNode,
+    dyna_nodes::{Card, Record, SourceFile},
+    keyword::KwOption,
+    parse::TextRange,
+};
+
+use crate::{
+    helper::normal,
+    ir::{parse, Source},
+    template::number,
+    transform::{define_transformation, Affine, IdKind, IncludeTransform, Transform},
+};
+
+/// one `*INCLUDE` and the file it found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Include {
+    /// file with the `*INCLUDE`
+    pub from: Source,
+    /// the line(s) of the file name
+    pub range: TextRange,
+    pub name: String,
+    /// `None` if it's nowhere to be found
+    pub file: Option<Source>,
+    /// in `Model::transforms`, what goes for the included file
+    pub transform: usize,
+    /// the `*INCLUDE` that got to `from`, `None` in the master deck
+    pub parent: Option<usize>,
+}
+
+/// a card as read by the solver
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Entry {
+    pub file: Source,
+    pub range: TextRange,
+    /// in `Model::transforms`
+    pub transform: usize,
+    /// in `Model::includes`, how the file was got to. `None` for the master deck
+    pub include: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Model {
+    /// every card of every file, one after the other like the solver reads them
+    pub deck: Vec<Entry>,
+    pub includes: Vec<Include>,
+    /// the first one is for the master deck, changes nothing
+    pub transforms: Vec<Transform>,
+    /// missing and cyclic includes, bad transforms, where they are
+    pub problems: Vec<(Source, TextRange, String)>,
+    /// paths an include was looked for at and no file was, for the db to read
+    pub missing: Vec<String>,
+}
+
+impl Model {
+    /// the deck as cards
+    pub fn cards(&self, db: &dyn crate::Db) -> Vec<(Entry, Card)> {
+        let mut cards = Vec::with_capacity(self.deck.len());
+        for &entry in &self.deck {
+            let root = parse(db, entry.file).node(db).syntax_node();
+            // a card of only a keyword is covered by its KEYWORD
+            let element = root.covering_element(entry.range);
+            let node = element.as_node().cloned().or_else(|| element.as_token()?.parent());
+            let card = node.and_then(|n| n.ancestors().find_map(Card::cast));
+            cards.extend(card.map(|c| (entry, c)));
+        }
+        cards
+    }
+
+    /// files in the model, each once
+    pub fn files(&self) -> Vec<Source> {
+        let mut files: Vec<Source> = vec![];
+        for entry in &self.deck {
+            if !files.contains(&entry.file) {
+                files.push(entry.file);
+            }
+        }
+        files
+    }
+
+    pub fn transform(&self, entry: &Entry) -> &Transform {
+        &self.transforms[entry.transform]
+    }
+
+    /// the `*INCLUDE`s that got to the entry, innermost first
+    pub fn include_chain(&self, entry: &Entry) -> Vec<usize> {
+        let mut chain = vec![];
+        let mut at = entry.include;
+        while let Some(i) = at {
+            chain.push(i);
+            at = self.includes[i].parent;
+        }
+        chain
+    }
+}
+
+#[salsa::tracked]
+pub fn model(db: &dyn crate::Db, main: Source) -> Model {
+    let dir = Path::new(main.path(db))
+        .parent()
+        .map_or(PathBuf::new(), Path::to_path_buf);
+    let mut walk = Walk {
+        db,
+        dir,
+        paths: vec![],
+        stack: vec![],
+        tranids: HashMap::new(),
+        model: Model {
+            deck: vec![],
+            includes: vec![],
+            transforms: vec![Transform::default()],
+            problems: vec![],
+            missing: vec![],
+        },
+    };
+    walk.file(main, None, 0);
+    walk.model
+}
+
+struct Walk<'a> {
+    db: &'a dyn crate::Db,
+    /// where the master deck is, the solver runs there
+    dir: PathBuf,
+    /// `*INCLUDE_PATH` and `*INCLUDE_PATH_RELATIVE` dirs read so far
+    paths: Vec<PathBuf>,
+    /// files being read, with the `*INCLUDE` that got there
+    stack: Vec<(Source, Option<usize>)>,
+    /// `*DEFINE_TRANSFORMATION`s read so far, by the ID the solver sees
+    tranids: HashMap<i64, Affine>,
+    model: Model,
+}
+
+impl Walk<'_> {
+    fn file(&mut self, source: Source, include: Option<usize>, transform: usize) {
+        let db = self.db;
+        self.stack.push((source, include));
+        let file: SourceFile = parse(db, source).node(db).tree();
+        for card in file.cards() {
+            let range = card.syntax().text_range();
+            let entry = Entry { file: source, range, transform, include };
+            self.model.deck.push(entry);
+            let Some(kw) = card.keyword() else {continue};
+            match kw.name().base() {
+                // the solver's cwd is the deck's dir, so both are next to the deck
+                "INCLUDE_PATH" | "INCLUDE_PATH_RELATIVE" => {
+                    let dirs = card.records().map(|r| line(&r));
+                    let dirs: Vec<_> = dirs.filter(|d| !d.is_empty()).map(|d| self.dir.join(d)).collect();
+                    self.paths.extend(dirs);
+                }
+                "INCLUDE" => {
+                    let Some((name, range)) = file_name(&mut card.records()) else {continue};
+                    self.include(source, name, range, transform);
+                }
+                "INCLUDE_TRANSFORM" => {
+                    let mut records = card.records();
+                    let Some((name, range)) = file_name(&mut records) else {continue};
+                    let values: Vec<Vec<_>> = records.map(|r| r.fields().map(|f| f.value()).collect()).collect();
+                    let inner = match IncludeTransform::read(&values) {
+                        Ok(t) => self.resolve(source, range, transform, t),
+                        Err(msg) => {
+                            self.model.problems.push((source, range, msg));
+                            Transform::default()
+                        }
+                    };
+                    let inner = self.model.transforms[transform].then(&inner);
+                    self.model.transforms.push(inner);
+                    let inner = self.model.transforms.len() - 1;
+                    self.include(source, name, range, inner);
+                }
+                "DEFINE_TRANSFORMATION" => self.define_transformation(source, &card, transform),
+                _ => {}
+            }
+        }
+        self.stack.pop();
+    }
+
+    fn include(&mut self, from: Source, name: String, range: TextRange, transform: usize) {
+        let db = self.db;
+        let parent = self.stack.last().and_then(|(_, inc)| *inc);
+        let Some(file) = self.find(&name) else {
+            let msg = format!("can't find `{name}`");
+            self.model.problems.push((from, range, msg));
+            let file = None;
+            self.model.includes.push(Include { from, range, name, file, transform, parent });
+            return;
+        };
+        let at = self.model.includes.len();
+        let include = Include { from, range, name, file: Some(file), transform, parent };
+        self.model.includes.push(include);
+        // every `*INCLUDE` on the way round gets told
+        if let Some(i) = self.stack.iter().position(|(s, _)| *s == file) {
+            let mut names: Vec<_> = self.stack[i..].iter().map(|(s, _)| short_name(db, *s)).collect();
+            names.push(short_name(db, file));
+            let msg = format!("cyclic include: {}", names.join(" -> "));
+            let ways = self.stack[i + 1..].iter().filter_map(|(_, inc)| *inc).chain([at]);
+            for inc in ways.collect::<Vec<_>>() {
+                let Include { from, range, .. } = self.model.includes[inc];
+                self.model.problems.push((from, range, msg.clone()));
+            }
+            return;
+        }
+        self.file(file, Some(at), transform);
+    }
+
+    /// the TRANID of an `*INCLUDE_TRANSFORM` in a file read with `outer`
+    fn resolve(&mut self, source: Source, range: TextRange, outer: usize, t: IncludeTransform) -> Transform {
+        if t.tranid == 0 {
+            return t.transform(None);
+        }
+        let tranid = self.model.transforms[outer].id(IdKind::Define, t.tranid);
+        let affine = self.tranids.get(&tranid);
+        if affine.is_none() {
+            let msg = format!("no *DEFINE_TRANSFORMATION {tranid} before");
+            self.model.problems.push((source, range, msg));
+        }
+        t.transform(affine)
+    }
+
+    /// `tranid` and then `option a1 .. a7` lines
+    fn define_transformation(&mut self, source: Source, card: &Card, transform: usize) {
+        let title = card.keyword().is_some_and(|k| k.name().has(KwOption::Title));
+        let mut records = card.records().skip(usize::from(title));
+        let Some(first) = records.next() else {return};
+        let Some(Ok(id)) = first.field(0).and_then(|f| f.value()).map(|v| v.parse::<i64>()) else {return};
+        let mut lines = vec![];
+        for record in records {
+            let values: Vec<_> = record.fields().map(|f| f.value().unwrap_or_default()).collect();
+            let Some(option) = values.first() else {continue};
+            let mut a = [0.; 7];
+            for (a, v) in a.iter_mut().zip(&values[1..]) {
+                *a = number(v).unwrap_or(0.);
+            }
+            lines.push((option.clone(), a));
+        }
+        let id = self.model.transforms[transform].id(IdKind::Define, id);
+        match define_transformation(&lines) {
+            Ok(affine) => {
+                self.tranids.insert(id, affine);
+            }
+            Err(msg) => {
+                let range = card.keyword().map_or(card.syntax().text_range(), |k| k.syntax().text_range());
+                self.model.problems.push((source, range, msg));
+            }
+        }
+    }
+
+    /// absolute as it is, else next to the deck, then in the `*INCLUDE_PATH`s
+    /// in the order they came. a file is there if the disk has it or it's open,
+    /// the places before it are kept for when a file turns up there
+    fn find(&mut self, name: &str) -> Option<Source> {
+        let name = Path::new(name);
+        let tries = match name.is_absolute() {
+            true => vec![name.to_path_buf()],
+            false => [&self.dir].into_iter().chain(&self.paths).map(|d| d.join(name)).collect(),
+        };
+        let db = self.db;
+        for path in tries.iter().map(|p| normal(p)) {
+            match db.files().paths(db).get(&path) {
+                Some(&f) if f.disk(db).is_some() || f.text(db).is_some() => return Some(f),
+                _ => self.model.missing.push(path),
+            }
+        }
+        None
+    }
+}
+
+/// a name too long for a line goes on the next, the line ending with ` +`
+fn file_name(records: &mut impl Iterator<Item = Record>) -> Option<(String, TextRange)> {
+    let mut name = String::new();
+    let mut range: Option<TextRange> = None;
+    for record in records {
+        let line = line(&record);
+        let line_range = record.line_range();
+        range = Some(range.map_or(line_range, |r| r.cover(line_range)));
+        match line.strip_suffix(" +") {
+            Some(part) => name.push_str(part.trim_end()),
+            None => {
+                name.push_str(&line);
+                break;
+            }
+        }
+    }
+    let range = range?;
+    (!name.is_empty()).then_some((name, range))
+}
+
+/// the data line of a record, trimmed
+fn line(record: &Record) -> String {
+    let range = record.line_range() - record.syntax().text_range().start();
+    record.syntax().text().to_string()[range].trim().to_string()
+}
+
+fn short_name(db: &dyn crate::Db, source: Source) -> String {
+    let path = Path::new(source.path(db));
+    path.file_name().map_or(path.to_string_lossy(), |n| n.to_string_lossy()).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use tempfile::TempDir;
+    use tower_lsp::lsp_types::TextDocumentContentChangeEvent;
+
+    use super::model;
+    use crate::{helper::key, ir::Source, RootDatabase};
+
+    /// an empty dir for the files of one test, gone after it
+    fn dir() -> TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        dir
+    }
+
+    fn path(dir: &Path, name: &str) -> String {
+        key(&dir.join(name))
+    }
+
+    fn messages(db: &RootDatabase, main: Source) -> Vec<String> {
+        model(db, main).problems.into_iter().map(|(_, _, msg)| msg).collect()
+    }
+
+    #[test]
+    fn read_in_order_through_include_paths() {
+        let tmp = dir();
+        let dir = tmp.path();
+        fs::write(dir.join("main.k"), "*INCLUDE\nsub/a.k\n*INCLUDE_PATH\nsub\n*INCLUDE\nb.k\n*PART\n").unwrap();
+        fs::write(dir.join("sub/a.k"), "*NODE\n*ELEMENT_SHELL\n").unwrap();
+        fs::write(dir.join("sub/b.k"), "*MAT_ELASTIC\n").unwrap();
+        let mut db = RootDatabase::new();
+        let main = db.load(&path(dir, "main.k"));
+        let model = model(&db, main);
+        let (a, b) = (db.input(&path(dir, "sub/a.k")), db.input(&path(dir, "sub/b.k")));
+        assert_eq!(model.files(), vec![main, a, b]);
+        let files: Vec<_> = model.deck.iter().map(|e| e.file).collect();
+        assert_eq!(files, vec![main, a, a, main, main, b, main]);
+        assert!(model.problems.is_empty());
+        assert_eq!(model.includes[1].name, "b.k");
+        // looked for next to the deck first
+        assert_eq!(model.missing, vec![path(dir, "b.k")]);
+    }
+
+    #[test]
+    fn missing_and_cyclic() {
+        let tmp = dir();
+        let dir = tmp.path();
+        fs::write(dir.join("main.k"), "*INCLUDE\nnone.k\n*INCLUDE\na.k\n").unwrap();
+        fs::write(dir.join("a.k"), "*INCLUDE\nmain.k\n").unwrap();
+        let mut db = RootDatabase::new();
+        let main = db.load(&path(dir, "main.k"));
+        let cyclic = "cyclic include: main.k -> a.k -> main.k";
+        // both `*INCLUDE`s on the way round
+        assert_eq!(messages(&db, main), vec!["can't find `none.k`", cyclic, cyclic]);
+    }
+
+    #[test]
+    fn files_made_later_are_found() {
+        let tmp = dir();
+        let dir = tmp.path();
+        fs::write(dir.join("main.k"), "*INCLUDE\nlater.k\n").unwrap();
+        let mut db = RootDatabase::new();
+        let main = db.load(&path(dir, "main.k"));
+        assert_eq!(messages(&db, main), vec!["can't find `later.k`"]);
+        fs::write(dir.join("later.k"), "*PART\n").unwrap();
+        db.refresh(&path(dir, "later.k"));
+        assert!(messages(&db, main).is_empty());
+        assert_eq!(model(&db, main).files(), vec![main, db.input(&path(dir, "later.k"))]);
+        fs::remove_file(dir.join("later.k")).unwrap();
+        db.refresh(&path(dir, "later.k"));
+        assert_eq!(messages(&db, main), vec!["can't find `later.k`"]);
+    }
+
+    #[test]
+    fn read_when_the_deck_changes() {
+        let tmp = dir();
+        let dir = tmp.path();
+        fs::write(dir.join("a.k"), "*PART\n").unwrap();
+        let mut db = RootDatabase::new();
+        let main = path(dir, "main.k");
+        db.open(&main, "*PART\n".to_string());
+        // typed in, never seen before
+        let all = |text: &str| TextDocumentContentChangeEvent { range: None, range_length: None, text: text.to_string() };
+        db.change(&main, vec![all("*INCLUDE\na.k\n*INCLUDE\nb.k\n")]).unwrap();
+        let main = db.input(&main);
+        assert_eq!(messages(&db, main), vec!["can't find `b.k`"]);
+        // made with no event for it, read with the next edit
+        fs::write(dir.join("b.k"), "*PART\n").unwrap();
+        db.change(&path(dir, "main.k"), vec![all("*INCLUDE\na.k\n*INCLUDE\nb.k\n$\n")]).unwrap();
+        assert!(messages(&db, main).is_empty());
+        assert_eq!(model(&db, main).files().len(), 3);
+    }
+
+    #[test]
+    fn open_files_are_the_same_input() {
+        let tmp = dir();
+        let dir = tmp.path();
+        fs::write(dir.join("main.k"), "*INCLUDE\n./sub/../a.k\n").unwrap();
+        fs::write(dir.join("a.k"), "*PART\n").unwrap();
+        let mut db = RootDatabase::new();
+        // as the editor names it
+        let uri = tower_lsp::lsp_types::Url::from_file_path(dir.join("a.k")).unwrap();
+        db.open(&crate::helper::path(&uri), "*PART\n*NODE\n".to_string());
+        let main = db.load(&path(dir, "main.k"));
+        let model = model(&db, main);
+        assert_eq!(model.files(), vec![main, db.input(&crate::helper::path(&uri))]);
+        assert_eq!(model.deck.len(), 3);
+        // not saved yet, still found
+        db.open(&path(dir, "new.k"), "*PART\n".to_string());
+        fs::write(dir.join("main.k"), "*INCLUDE\nnew.k\n").unwrap();
+        db.refresh(&path(dir, "main.k"));
+        assert!(messages(&db, main).is_empty());
+    }
+}
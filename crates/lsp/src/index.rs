@@ -0,0 +1,653 @@
+//! every entity the model defines and every field naming one, as the
+//! `def=` and `ref=` marks of the keyword schema say
+
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+};
+
+use syntax::{
+    ast::AstNode,
+    parse::{Format, TextRange, TextSize},
+};
+
+use crate::{
+    check::{columns, slots, value, walk, width},
+    include::{model, Entry},
+    ir::{parse, Source},
+    param::{params, Params},
+    schema::{Entity, FieldSchema, KeywordSchema},
+    template::number,
+    transform::{IdKind, Transform},
+};
+
+/// where an entity gets its ID
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Definition {
+    pub entity: Entity,
+    /// as the solver sees it, include offsets added
+    pub id: i64,
+    pub file: Source,
+    /// of the value
+    pub range: TextRange,
+    /// the column the value is in, `None` in free format
+    pub slot: Option<TextRange>,
+    /// of the column, the slot stops short where the line does
+    pub width: usize,
+    /// the keyword, `MAT_ELASTIC_TITLE`
+    pub keyword: String,
+    /// of `_TITLE` or `_ID`
+    pub title: Option<String>,
+}
+
+/// a field naming an entity
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Use {
+    pub entity: Entity,
+    /// as the solver sees it
+    pub id: i64,
+    pub file: Source,
+    /// of the value
+    pub range: TextRange,
+    /// the column the value is in, `None` in free format
+    pub slot: Option<TextRange>,
+    /// of the column, the slot stops short where the line does
+    pub width: usize,
+}
+
+/// the IDs of a `*NODE` or `*ELEMENT_` card. a mesh has millions, they're
+/// kept by the card and not as definitions and uses each
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mesh {
+    pub file: Source,
+    /// of the card
+    pub range: TextRange,
+    /// the keyword, `ELEMENT_SHELL`
+    pub keyword: String,
+    /// in the order of the records
+    pub ids: Vec<MeshId>,
+}
+
+/// what a definition or use is without what the card has for all of them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MeshId {
+    pub entity: Entity,
+    /// or names it
+    pub defines: bool,
+    /// as the solver sees it
+    pub id: i64,
+    /// of the value
+    pub range: TextRange,
+    /// the column the value is in, `None` in free format
+    pub slot: Option<TextRange>,
+    /// of the column, no ID column is wider than 20
+    pub width: u8,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Index {
+    /// in the order the solver reads them, but the mesh's
+    pub definitions: Vec<Definition>,
+    pub uses: Vec<Use>,
+    pub mesh: Vec<Mesh>,
+    /// keywords in the model the schema marks no ID of, they may define anything
+    pub unread: Vec<String>,
+}
+
+/// a bad ID, with the other places it's about
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    pub file: Source,
+    pub range: TextRange,
+    pub message: String,
+    pub related: Vec<(Source, TextRange, String)>,
+}
+
+impl Index {
+    /// the mesh's too
+    pub fn definitions_of(&self, entity: Entity, id: i64) -> impl Iterator<Item = Definition> + '_ {
+        let own = self.definitions.iter().filter(move |d| d.entity == entity && d.id == id);
+        let mesh = self.mesh_ids(true).filter(move |(_, i)| i.entity == entity && i.id == id);
+        own.cloned().chain(mesh.map(|(m, i)| Definition {
+            entity: i.entity,
+            id: i.id,
+            file: m.file,
+            range: i.range,
+            slot: i.slot,
+            width: i.width.into(),
+            keyword: m.keyword.clone(),
+            title: None,
+        }))
+    }
+
+    /// the mesh's too
+    pub fn uses_of(&self, entity: Entity, id: i64) -> impl Iterator<Item = Use> + '_ {
+        let own = self.uses.iter().filter(move |u| u.entity == entity && u.id == id);
+        let mesh = self.mesh_ids(false).filter(move |(_, i)| i.entity == entity && i.id == id);
+        own.copied().chain(mesh.map(|(m, i)| Use {
+            entity: i.entity,
+            id: i.id,
+            file: m.file,
+            range: i.range,
+            slot: i.slot,
+            width: i.width.into(),
+        }))
+    }
+
+    /// every ID defined, the mesh's too, with the file and range of it
+    pub fn defined(&self) -> impl Iterator<Item = (Entity, i64, Source, TextRange)> + '_ {
+        let own = self.definitions.iter().map(|d| (d.entity, d.id, d.file, d.range));
+        own.chain(self.mesh_ids(true).map(|(m, i)| (i.entity, i.id, m.file, i.range)))
+    }
+
+    /// every ID used, the mesh's too, with the file and range of it
+    pub fn used(&self) -> impl Iterator<Item = (Entity, i64, Source, TextRange)> + '_ {
+        let own = self.uses.iter().map(|u| (u.entity, u.id, u.file, u.range));
+        own.chain(self.mesh_ids(false).map(|(m, i)| (i.entity, i.id, m.file, i.range)))
+    }
+
+    fn mesh_ids(&self, defines: bool) -> impl Iterator<Item = (&Mesh, &MeshId)> {
+        let ids = self.mesh.iter().flat_map(|m| m.ids.iter().map(move |i| (m, i)));
+        ids.filter(move |(_, i)| i.defines == defines)
+    }
+
+    /// some keyword there's no reading of could be defining them
+    pub fn may_define(&self, entity: Entity) -> bool {
+        let starts = keywords(entity);
+        self.unread.iter().any(|k| starts.iter().any(|s| k.starts_with(s)))
+    }
+
+    /// the entity whose ID is at `offset`, and where the ID is
+    pub fn at(&self, file: Source, offset: TextSize) -> Option<(Entity, i64, TextRange)> {
+        self.defined()
+            .chain(self.used())
+            .find(|(_, _, f, range)| *f == file && range.contains_inclusive(offset))
+            .map(|(entity, id, _, range)| (entity, id, range))
+    }
+}
+
+#[salsa::tracked]
+pub fn index(db: &dyn crate::Db, main: Source) -> Index {
+    let model = model(db, main);
+    let params = params(db, main);
+    let schema = db.schema().schema(db);
+    let mut index = Index::default();
+    let mut formats: HashMap<Source, Format> = HashMap::new();
+    // by the name, with whether it's a mesh one
+    let mut schemas: HashMap<String, Option<(KeywordSchema, bool)>> = HashMap::new();
+    for (entry, card) in model.cards(db) {
+        let Some(kw) = card.keyword() else {continue};
+        let name = kw.name();
+        let kw_schema = schemas.entry(name.to_string()).or_insert_with(|| {
+            let kw_schema = schema.get(&name)?;
+            let mut fields = kw_schema.cards.iter().flat_map(|c| &c.fields);
+            if !fields.clone().any(marked) {
+                return None;
+            }
+            let mesh = fields.any(|f| matches!(f.defines, Some(Entity::Node | Entity::Element)));
+            Some((kw_schema, mesh))
+        });
+        let Some((kw_schema, mesh)) = kw_schema else {
+            let name = name.to_string();
+            if !index.unread.contains(&name) {
+                index.unread.push(name);
+            }
+            continue;
+        };
+        let format = *formats
+            .entry(entry.file)
+            .or_insert_with(|| parse(db, entry.file).node(db).tree().format());
+        let format = card.format(format);
+        // what the tree is of, the IDs are cut out of it
+        let text = match entry.file.text(db) {
+            Some(text) => text.as_str(),
+            None => entry.file.disk(db).as_deref().unwrap_or_default(),
+        };
+        let ids = Ids {
+            entry: &entry,
+            transform: model.transform(&entry),
+            params: &params,
+        };
+        let mut mesh = mesh.then(|| Mesh {
+            file: entry.file,
+            range: card.syntax().text_range(),
+            keyword: kw_schema.name.clone(),
+            ids: vec![],
+        });
+        let mut title = None;
+        walk(kw_schema, &card, format, |schema, record, values| {
+            if mesh.is_none() && (schema.is_title() || schema.is_id()) {
+                let text = slots(schema, record, format).pop().map(|(_, _, t)| t);
+                title = text.map(|t| t.trim().to_string()).filter(|t| !t.is_empty());
+            }
+            if !schema.fields.iter().any(marked) {
+                return;
+            }
+            let free = record.is_free_format();
+            columns(schema, record, format, |field, slot| {
+                if !marked(field) {
+                    return;
+                }
+                let Some(text) = text.get(Range::<usize>::from(slot)) else {return};
+                let Some((id, range)) = ids.read(text, slot) else {return};
+                let slot = (!free).then_some(slot);
+                let width = width(field, format);
+                let defines = field.defines.filter(|_| id > 0);
+                let refers = refers(field, id, values);
+                if let Some(mesh) = &mut mesh {
+                    let found = defines.map(|e| (e, true)).into_iter().chain(refers.map(|e| (e, false)));
+                    for (entity, defines) in found {
+                        let id = ids.solver(entity, id.abs());
+                        let width = width as u8;
+                        mesh.ids.push(MeshId { entity, defines, id, range, slot, width });
+                    }
+                    return;
+                }
+                if let Some(entity) = defines {
+                    index.definitions.push(Definition {
+                        entity,
+                        id: ids.solver(entity, id),
+                        file: entry.file,
+                        range,
+                        slot,
+                        width,
+                        keyword: kw_schema.name.clone(),
+                        title: title.clone(),
+                    });
+                }
+                let Some(entity) = refers else {return};
+                let id = ids.solver(entity, id.abs());
+                index.uses.push(Use { entity, id, file: entry.file, range, slot, width });
+            });
+        });
+        index.mesh.extend(mesh);
+    }
+    index
+}
+
+/// uses of IDs nothing defines, IDs defined twice
+#[salsa::tracked]
+pub fn problems(db: &dyn crate::Db, main: Source) -> Vec<Problem> {
+    let index = index(db, main);
+    let mut problems = vec![];
+    let mut first: HashMap<(Entity, i64), (Source, TextRange)> = HashMap::new();
+    for (entity, id, file, range) in index.defined() {
+        let Some((other, at)) = first.get(&(entity, id)) else {
+            first.insert((entity, id), (file, range));
+            continue;
+        };
+        problems.push(Problem {
+            file,
+            range,
+            message: format!("{entity} {id} is defined twice"),
+            related: vec![(*other, *at, "the first one".to_string())],
+        });
+    }
+    // a file not found may have had them
+    if model(db, main).includes.iter().any(|i| i.file.is_none()) {
+        return problems;
+    }
+    for (entity, id, file, range) in index.used() {
+        if first.contains_key(&(entity, id)) || index.may_define(entity) {
+            continue;
+        }
+        problems.push(Problem {
+            file,
+            range,
+            message: format!("{entity} {id} is not defined"),
+            related: vec![],
+        });
+    }
+    problems
+}
+
+/// definitions nothing uses, but of the allowed ones
+#[salsa::tracked]
+pub fn unused(db: &dyn crate::Db, main: Source) -> Vec<Problem> {
+    let index = index(db, main);
+    let allow = db.config().allow_unused(db);
+    let used: HashSet<_> = index.used().map(|(entity, id, _, _)| (entity, id)).collect();
+    // a file not found may use them
+    if model(db, main).includes.iter().any(|i| i.file.is_none()) {
+        return vec![];
+    }
+    // the mesh's aren't, nothing names an element, a node is for its elements to name
+    let unused = index.definitions.iter().filter(|d| {
+        !used.contains(&(d.entity, d.id)) && !allow.iter().any(|a| a.allows(d.entity, d.id))
+    });
+    unused
+        .map(|d| Problem {
+            file: d.file,
+            range: d.range,
+            message: format!("{} {} is not used", d.entity, d.id),
+            related: vec![],
+        })
+        .collect()
+}
+
+/// an entity, or some IDs of it, nobody needs to use, `curve`,
+/// `curve 1000` or `curve 1000-1999`, solver IDs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Allow {
+    pub entity: Entity,
+    pub ids: Option<(i64, i64)>,
+}
+
+impl Allow {
+    pub fn parse(text: &str) -> Option<Allow> {
+        let mut words = text.split_whitespace();
+        let entity = Entity::from_word(words.next()?)?;
+        let ids = match words.next() {
+            None => None,
+            Some(ids) => {
+                let (from, to) = ids.split_once('-').unwrap_or((ids, ids));
+                Some((from.parse().ok()?, to.parse().ok()?))
+            }
+        };
+        match words.next() {
+            None => Some(Allow { entity, ids }),
+            Some(_) => None,
+        }
+    }
+
+    pub fn allows(&self, entity: Entity, id: i64) -> bool {
+        self.entity == entity && self.ids.map_or(true, |(from, to)| (from..=to).contains(&id))
+    }
+}
+
+/// what keywords `entity`s are defined by start with
+fn keywords(entity: Entity) -> &'static [&'static str] {
+    match entity {
+        Entity::Part => &["PART"],
+        Entity::Section => &["SECTION_"],
+        Entity::Material => &["MAT_"],
+        Entity::Eos => &["EOS_"],
+        Entity::Hourglass => &["HOURGLASS"],
+        Entity::Curve => &["DEFINE_CURVE", "DEFINE_TABLE", "DEFINE_FUNCTION"],
+        Entity::Box => &["DEFINE_BOX"],
+        Entity::Coordinate => &["DEFINE_COORDINATE"],
+        Entity::Vector => &["DEFINE_VECTOR"],
+        Entity::Node => &["NODE"],
+        Entity::Element => &["ELEMENT_"],
+        Entity::NodeSet => &["SET_NODE"],
+        Entity::PartSet => &["SET_PART"],
+        Entity::SegmentSet => &["SET_SEGMENT"],
+        Entity::ShellSet => &["SET_SHELL"],
+        Entity::SolidSet => &["SET_SOLID"],
+        Entity::BeamSet => &["SET_BEAM"],
+        Entity::DiscreteSet => &["SET_DISCRETE"],
+    }
+}
+
+/// a field with IDs in it
+fn marked(field: &FieldSchema) -> bool {
+    field.defines.is_some() || !field.refers.is_empty() || field.curve
+}
+
+/// the first `ref=` whose condition holds for a positive `id`, a negative
+/// one of a `curve` field is a curve
+fn refers(field: &FieldSchema, id: i64, values: &[(String, String)]) -> Option<Entity> {
+    match (id < 0, field.curve) {
+        (true, true) => return Some(Entity::Curve),
+        (true, false) => return None,
+        _ => {}
+    }
+    field
+        .refers
+        .iter()
+        .find(|(_, when)| when.as_ref().map_or(true, |c| c.holds(value(values, &c.field))))
+        .map(|(entity, _)| *entity)
+}
+
+/// reads IDs of one card
+struct Ids<'a> {
+    entry: &'a Entry,
+    transform: &'a Transform,
+    params: &'a Params,
+}
+
+impl Ids<'_> {
+    /// the ID written in a field and where it is, blank and 0 are none
+    fn read(&self, text: &str, slot: TextRange) -> Option<(i64, TextRange)> {
+        let value = text.trim();
+        let lead = TextSize::of(&text[..text.len() - text.trim_start().len()]);
+        let range = TextRange::at(slot.start() + lead, TextSize::of(value));
+        let id = match value.trim_start_matches('-').starts_with('&') {
+            // `&name`, what the parameter is worth
+            true => {
+                let reference = self.params.references.iter().find(|r| {
+                    r.file == self.entry.file && range.contains_range(r.range)
+                })?;
+                let x = self.params.params[reference.param?].value.as_ref()?.number()?;
+                match value.starts_with('-') {
+                    true => -x,
+                    false => x,
+                }
+            }
+            false => number(value)?,
+        };
+        if id.fract() != 0. || id == 0. {
+            return None;
+        }
+        Some((id as i64, range))
+    }
+
+    /// what the solver sees, include offsets added
+    fn solver(&self, entity: Entity, id: i64) -> i64 {
+        self.transform.id(id_kind(entity), id)
+    }
+}
+
+/// which `*INCLUDE_TRANSFORM` offset goes to the IDs
+pub fn id_kind(entity: Entity) -> IdKind {
+    match entity {
+        Entity::Node => IdKind::Node,
+        Entity::Element => IdKind::Element,
+        Entity::Part => IdKind::Part,
+        Entity::Material => IdKind::Material,
+        Entity::NodeSet
+        | Entity::PartSet
+        | Entity::SegmentSet
+        | Entity::ShellSet
+        | Entity::SolidSet
+        | Entity::BeamSet
+        | Entity::DiscreteSet => IdKind::Set,
+        Entity::Curve => IdKind::Curve,
+        Entity::Box | Entity::Coordinate | Entity::Vector => IdKind::Define,
+        Entity::Eos => IdKind::Eos,
+        Entity::Hourglass => IdKind::Hourglass,
+        Entity::Section => IdKind::Other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use syntax::parse::TextSize;
+
+    use super::{index, problems, unused, Allow};
+    use crate::{schema::Entity, RootDatabase};
+
+    /// right-aligned in 10 columns each
+    fn line(values: &[&str]) -> String {
+        values.iter().map(|v| format!("{v:>10}")).collect::<String>() + "\n"
+    }
+
+    fn open(files: &[(&str, String)]) -> RootDatabase {
+        let mut db = RootDatabase::new();
+        for (path, text) in files {
+            db.open(path, text.clone());
+        }
+        db
+    }
+
+    fn mat(id: &str) -> String {
+        format!("*MAT_ELASTIC\n{}", line(&[id, "7.8e-9", "210000.", "0.3"]))
+    }
+
+    fn part(title: &str, values: &[&str]) -> String {
+        format!("*PART\n{title}\n{}", line(values))
+    }
+
+    #[test]
+    fn definitions_and_uses_in_solver_ids() {
+        let main = format!(
+            "{}{}*INCLUDE_TRANSFORM\n/dbk/inc.k\n{}",
+            mat("5"),
+            part("bumper", &["1", "1", "5"]),
+            line(&["0", "0", "1000", "100"]),
+        );
+        let inc = format!("{}{}", mat("5"), part("beam", &["1", "1", "5"]));
+        let mut db = open(&[("/dbk/main.k", main), ("/dbk/inc.k", inc)]);
+        let main = db.input("/dbk/main.k");
+        let index = index(&db, main);
+        let defs: Vec<_> = index.definitions.iter().map(|d| (d.entity, d.id, d.file == main, d.title.as_deref())).collect();
+        assert_eq!(defs, vec![
+            (Entity::Material, 5, true, None),
+            (Entity::Part, 1, true, Some("bumper")),
+            (Entity::Material, 105, false, None),
+            (Entity::Part, 1001, false, Some("beam")),
+        ]);
+        assert_eq!(index.definitions[1].keyword, "PART");
+        let uses: Vec<_> = index.uses.iter().map(|u| (u.entity, u.id, u.file == main)).collect();
+        assert_eq!(uses, vec![
+            (Entity::Section, 1, true),
+            (Entity::Material, 5, true),
+            (Entity::Section, 1, false),
+            (Entity::Material, 105, false),
+        ]);
+        // the value without its blanks, in its column
+        let u = &index.uses[1];
+        assert_eq!((usize::from(u.range.len()), u.slot.map(|s| usize::from(s.len())), u.width), (1, Some(10), 10));
+        assert_eq!(index.definitions_of(Entity::Material, 105).count(), 1);
+        assert_eq!(index.uses_of(Entity::Section, 1).count(), 2);
+    }
+
+    #[test]
+    fn parameters_curves_and_unread_keywords() {
+        let text = format!(
+            "*PARAMETER\n{:<10}{:>10}\n{}*DEFINE_CURVE\n{}{:>20}{:>20}\n*CONTROL_TIMESTEP\n{}*MAT_USER_DEFINED_MATERIAL_MODELS\n",
+            "I     mat",
+            "7",
+            mat("&mat"),
+            line(&["3"]),
+            "0.0",
+            "0.0",
+            line(&["0.0", "-3", "0", "0.0", "0.0", "4"]),
+        );
+        let mut db = open(&[("/dbk/main.k", text.clone())]);
+        let main = db.input("/dbk/main.k");
+        let index = index(&db, main);
+        let defs: Vec<_> = index.definitions.iter().map(|d| (d.entity, d.id)).collect();
+        assert_eq!(defs, vec![(Entity::Material, 7), (Entity::Curve, 3)]);
+        // a negative `tssfac` is a curve, `lctim` always is
+        let uses: Vec<_> = index.uses.iter().map(|u| (u.entity, u.id)).collect();
+        assert_eq!(uses, vec![(Entity::Curve, 3), (Entity::Curve, 4)]);
+        let at = TextSize::from(text.find("&mat").unwrap() as u32 + 1);
+        let (entity, id, range) = index.at(main, at).unwrap();
+        assert_eq!((entity, id, &text[range]), (Entity::Material, 7, "&mat"));
+        assert!(index.unread.contains(&"MAT_USER_DEFINED_MATERIAL_MODELS".to_string()));
+        assert!(index.may_define(Entity::Material));
+        assert!(!index.may_define(Entity::Part));
+    }
+
+    #[test]
+    fn undefined_and_defined_twice() {
+        let inc = format!("{}{}", mat("5"), part("bumper", &["1", "1", "6"]));
+        let main = format!("{}*INCLUDE\n/dbk/inc.k\n", mat("5"));
+        let mut db = open(&[("/dbk/main.k", main.clone()), ("/dbk/inc.k", inc.clone())]);
+        let (main, inc) = (db.input("/dbk/main.k"), db.input("/dbk/inc.k"));
+        let found = problems(&db, main);
+        let messages: Vec<_> = found.iter().map(|p| (p.file == inc, p.message.as_str())).collect();
+        assert_eq!(messages, vec![
+            (true, "material 5 is defined twice"),
+            (true, "section 1 is not defined"),
+            (true, "material 6 is not defined"),
+        ]);
+        // the one in the main deck comes first
+        let related: Vec<_> = found[0].related.iter().map(|(f, _, msg)| (*f == main, msg.as_str())).collect();
+        assert_eq!(related, vec![(true, "the first one")]);
+    }
+
+    #[test]
+    fn what_may_be_defined_elsewhere() {
+        // a keyword no ID of is read may define materials
+        let text = format!("*MAT_USER_DEFINED_MATERIAL_MODELS\n{}", part("bumper", &["1", "1", "6"]));
+        let mut db = open(&[("/dbk/main.k", text)]);
+        let main = db.input("/dbk/main.k");
+        let messages: Vec<_> = problems(&db, main).into_iter().map(|p| p.message).collect();
+        assert_eq!(messages, vec!["section 1 is not defined"]);
+        // so may an include not found, twice is twice still
+        let text = format!("{}{}*INCLUDE\n/dbk/none.k\n{}", mat("5"), mat("5"), part("bumper", &["1", "1", "6"]));
+        let mut db = open(&[("/dbk/main.k", text)]);
+        let main = db.input("/dbk/main.k");
+        let messages: Vec<_> = problems(&db, main).into_iter().map(|p| p.message).collect();
+        assert_eq!(messages, vec!["material 5 is defined twice"]);
+    }
+
+    #[test]
+    fn mesh_ids_by_the_card() {
+        let node = |id: &str| format!("{id:>8}{:>16}{:>16}{:>16}\n", "0.0", "0.0", "0.0");
+        let text = format!(
+            "{}*SECTION_SHELL\n{}{}*NODE\n{}{}{}*ELEMENT_SHELL\n{:>8}{:>8}{:>8}{:>8}{:>8}\n",
+            mat("5"),
+            line(&["1"]),
+            part("bumper", &["1", "1", "5"]),
+            node("1"),
+            node("2"),
+            node("2"),
+            1,
+            1,
+            1,
+            2,
+            3,
+        );
+        let mut db = open(&[("/dbk/main.k", text.clone())]);
+        let main = db.input("/dbk/main.k");
+        let index = index(&db, main);
+        // one for each card, none of them a definition or use of its own
+        let cards: Vec<_> = index.mesh.iter().map(|m| (m.keyword.as_str(), m.ids.len())).collect();
+        assert_eq!(cards, vec![("NODE", 3), ("ELEMENT_SHELL", 5)]);
+        assert!(index.definitions.iter().all(|d| !matches!(d.entity, Entity::Node | Entity::Element)));
+        assert!(index.uses.iter().all(|u| !matches!(u.entity, Entity::Node | Entity::Part)));
+        let node = index.definitions_of(Entity::Node, 1).next().unwrap();
+        assert_eq!((node.keyword.as_str(), node.width, &text[node.range]), ("NODE", 8, "1"));
+        assert_eq!(index.uses_of(Entity::Part, 1).count(), 1);
+        assert_eq!(index.uses_of(Entity::Node, 2).count(), 1);
+        let messages: Vec<_> = problems(&db, main).into_iter().map(|p| p.message).collect();
+        assert_eq!(messages, vec!["node 2 is defined twice", "node 3 is not defined"]);
+        // the elements use the part
+        assert!(unused(&db, main).is_empty());
+    }
+
+    #[test]
+    fn allow_lists() {
+        assert_eq!(Allow::parse("curve"), Some(Allow { entity: Entity::Curve, ids: None }));
+        let some = Allow::parse(" part  1000-1999 ").unwrap();
+        assert_eq!(some, Allow { entity: Entity::Part, ids: Some((1000, 1999)) });
+        assert!(some.allows(Entity::Part, 1000) && some.allows(Entity::Part, 1999));
+        assert!(!some.allows(Entity::Part, 2000) && !some.allows(Entity::Material, 1500));
+        assert!(Allow::parse("mat 7").unwrap().allows(Entity::Material, 7));
+        for bad in ["", "material", "part x", "part 1-", "part 1 2"] {
+            assert_eq!(Allow::parse(bad), None, "{bad}");
+        }
+    }
+
+    #[test]
+    fn definitions_nothing_uses() {
+        let text = format!(
+            "{}{}*SECTION_SHELL\n{}{}*DEFINE_CURVE\n{}*NODE\n{:>8}\n",
+            mat("5"),
+            mat("6"),
+            line(&["1"]),
+            part("bumper", &["1", "1", "5"]),
+            line(&["3"]),
+            1,
+        );
+        let mut db = open(&[("/dbk/main.k", text)]);
+        let main = db.input("/dbk/main.k");
+        let messages = |db: &RootDatabase| unused(db, main).into_iter().map(|p| p.message).collect::<Vec<_>>();
+        assert_eq!(messages(&db), vec!["material 6 is not used", "part 1 is not used", "curve 3 is not used"]);
+        db.allow_unused(vec![Allow::parse("curve").unwrap(), Allow::parse("part 1-9").unwrap()]);
+        assert_eq!(messages(&db), vec!["material 6 is not used"]);
+    }
+}
@@ -1,129 +1,163 @@
-use std::{fs, sync::Arc};
+use std::collections::BTreeMap;
 
+use salsa::Accumulator;
 use syntax::{
-    dyna_nodes::SourceFile,
+    ast::AstNode,
+    dyna_nodes::{KeyWord, SourceFile},
     parse::{parse_text, GreenNode, Parse},
-    reparsing::reparse_card,
-    syntax_node::{SyntaxKind, SyntaxNode},
+    syntax_node::SyntaxKind,
 };
 
-use text_edit::{TextEdit, TextSize};
-use tower_lsp::lsp_types::{Diagnostic, Position, Range, TextDocumentContentChangeEvent};
+use tower_lsp::lsp_types::{
+    Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity, Position, Range,
+};
 
 use crate::{
-    helper::{range, user_edit},
+    check::check_card,
+    helper::{location, range},
+    include::model,
+    index::{problems, unused, Allow},
     line_index::LineIndex,
+    param::params,
+    schema::Schema,
 };
 
 #[salsa::input]
 pub struct Source {
     #[return_ref]
-    path: String,
+    pub path: String,
+    /// what the editor has, `None` when it's not open and the disk is read
+    #[return_ref]
+    pub text: Option<String>,
+    /// tree of `text` when the edits could be reparsed in place
+    pub tree: Option<Parse<SourceFile>>,
+    /// what's on disk, `None` if there's no such file. read again when it changes
+    #[return_ref]
+    pub disk: Option<String>,
+}
+
+/// every file the db knows by the paths it was looked for at, one input can
+/// have a few. the model only reads it, the db fills it
+#[salsa::input]
+pub struct Files {
+    #[return_ref]
+    pub paths: BTreeMap<String, Source>,
+}
+
+/// keyword templates, builtin for now
+#[salsa::input]
+pub struct Keywords {
+    #[return_ref]
+    pub schema: Schema,
+}
+
+/// what the editor's settings say
+#[salsa::input]
+pub struct Config {
+    /// definitions not to warn about when nothing uses them
+    #[return_ref]
+    pub allow_unused: Vec<Allow>,
 }
 
 #[salsa::tracked]
-pub struct SourceProgram {
+pub struct SourceProgram<'db> {
+    #[tracked]
     #[return_ref]
     pub lines: LineIndex,
+    #[tracked]
     pub node: Parse<SourceFile>,
 }
 
 #[salsa::tracked]
-pub struct Card {
+pub struct Card<'db> {
     pub node: GreenNode,
 }
 
-#[salsa::input]
-pub struct Diff {
-    edits: Vec<TextDocumentContentChangeEvent>,
-}
-
 #[salsa::accumulator]
-pub struct Diagnostics(Diagnostic);
+pub struct Diagnostics(pub Diagnostic);
 
 #[salsa::tracked]
-pub fn parse(db: &dyn crate::Db, path: Source) -> SourceProgram {
-    let file = fs::read_to_string(path.path(db));
-    let f = match file {
-        Ok(f) => f,
-        Err(_) => String::new(),
+pub fn parse(db: &dyn crate::Db, path: Source) -> SourceProgram<'_> {
+    let f = match path.text(db) {
+        Some(f) => f.clone(),
+        None => path.disk(db).clone().unwrap_or_default(),
     };
     let lines = LineIndex::new(&f);
-    let node = parse_text(&f);
+    let node = path.tree(db).unwrap_or_else(|| parse_text(&f));
     SourceProgram::new(db, lines, node)
 }
 
+/// diagnostics of `source`, IDs and includes as the deck `main` sees them
 #[salsa::tracked]
-pub fn foo(db: &dyn crate::Db, source: SourceProgram, diff: Diff) {
-    let (cst, lines) = (source.node(db), source.lines(db));
-    let edits = user_edit(lines, diff.edits(db));
-    for i in edits {
-        let Some((green,err)) = reparse_card(&cst.syntax_node(), &i) else {continue;};
-        let diags = err.iter().map(|c| {
-            let range = range(&lines, c.range());
-            let msg = c.to_string();
-            Diagnostic::new_simple(range, msg)
-        });
-        for e in diags {
-            Diagnostics::push(db, e)
-        }
-    }
-}
-
-#[salsa::tracked]
-pub fn compile(db: &dyn crate::Db, source: Source, edit: Option<Diff>) {
-    let source = parse(db, source);
-    let (cst, lines) = (source.node(db), source.lines(db));
+pub fn compile(db: &dyn crate::Db, main: Source, source: Source) {
+    let program = parse(db, source);
+    let (cst, lines) = (program.node(db), program.lines(db));
     let err = cst.errors.clone();
     let diags = err.iter().map(|c| {
-        let range = range(&lines, c.range());
+        let range = range(lines, c.range());
         let msg = c.to_string();
         Diagnostic::new_simple(range, msg)
     });
 
+    let includes = model(db, main).problems.into_iter();
+    for (file, rng, msg) in includes.chain(params(db, main).problems) {
+        if file == source {
+            Diagnostics(Diagnostic::new_simple(range(lines, rng), msg)).accumulate(db);
+        }
+    }
+
+    let ids = problems(db, main).into_iter().map(|p| (p, DiagnosticSeverity::ERROR));
+    let unused = unused(db, main).into_iter().map(|p| (p, DiagnosticSeverity::WARNING));
+    for (p, severity) in ids.chain(unused) {
+        if p.file != source {
+            continue;
+        }
+        let related = p.related.into_iter().filter_map(|(file, rng, message)| {
+            let location = location(db, file, rng)?;
+            Some(DiagnosticRelatedInformation { location, message })
+        });
+        Diagnostics(Diagnostic {
+            severity: Some(severity),
+            related_information: Some(related.collect()),
+            ..Diagnostic::new_simple(range(lines, p.range), p.message)
+        })
+        .accumulate(db);
+    }
+
+    let file = cst.tree();
+    let format = file.format();
+    let schema = db.schema().schema(db);
+    for card in file.cards() {
+        let Some(kw) = card.keyword().and_then(|k| schema.get(&k.name())) else {continue};
+        for (rng, msg) in check_card(&kw, &card, card.format(format)) {
+            Diagnostics(Diagnostic::new_simple(range(lines, rng), msg)).accumulate(db);
+        }
+    }
+
     for i in cst.to_syntax().syntax_node().descendants() {
-        let rng = range(&lines, i.text_range());
-        match i.kind() {
-            SyntaxKind::GEOMETRY => {
-                Diagnostics::push(db, Diagnostic::new_simple(rng, "here a geo!".to_string()))
-            }
-            SyntaxKind::CARD => {
-                let mut card_node = i.descendants();
-                let Some(kwd) = card_node.find(|kd| kd.kind() == SyntaxKind::KEYWORD) else {continue};
-                let (wd, rg) = (kwd.text().to_string(), kwd.text_range());
-                if wd.trim() == "*MAT_ENHANCED_COMPOSITE_DAMAGE_TITLE" {
-                    let rng = range(lines, rg);
-                    Diagnostics::push(
-                        db,
-                        Diagnostic::new_simple(rng, "sorry I only recogonize this one".to_string()),
-                    );
-                    match card_node.next() {
-                        Some(nd) => {
-                            if nd.kind() == SyntaxKind::DECK {
-                                let rng: Range = range(lines, nd.text_range());
-                                let green = nd.green().into_owned();
-                                mat_54(db, Card::new(db, green), rng.start.line)
-                            }
-                        }
-                        None => {}
-                    }
-                }
+        if i.kind() != SyntaxKind::CARD {
+            continue;
+        }
+        let mut card_node = i.descendants();
+        let Some(kwd) = card_node.find_map(KeyWord::cast) else {continue};
+        if kwd.name().base() == "MAT_ENHANCED_COMPOSITE_DAMAGE" {
+            let rng = range(lines, kwd.syntax().text_range());
+            Diagnostics(Diagnostic::new_simple(rng, "sorry I only recogonize this one".to_string())).accumulate(db);
+            if let Some(nd) = card_node.next().filter(|nd| nd.kind() == SyntaxKind::DECK) {
+                let rng: Range = range(lines, nd.text_range());
+                let green = nd.green().into_owned();
+                mat_54(db, Card::new(db, green), rng.start.line)
             }
-            _ => {}
         }
     }
 
     for e in diags {
-        Diagnostics::push(db, e)
+        Diagnostics(e).accumulate(db)
     }
-
-    if let Some(diff) = edit {
-        foo(db, source, diff)
-    };
 }
 
 #[salsa::tracked]
-pub fn mat_54(db: &dyn crate::Db, card: Card, line: u32) {
+pub fn mat_54<'db>(db: &'db dyn crate::Db, card: Card<'db>, line: u32) {
     let line = line - 1;
     let node = card.node(db);
     let pos = Position {
@@ -131,5 +165,5 @@ pub fn mat_54(db: &dyn crate::Db, card: Card, line: u32) {
         ..Default::default()
     };
     let e = Diagnostic::new_simple(Range::new(pos, pos), format!("{:?}", node.kind()));
-    Diagnostics::push(db, e);
+    Diagnostics(e).accumulate(db);
 }
@@ -1,49 +1,217 @@
+pub mod check;
+pub mod complete;
+pub mod expr;
 pub mod helper;
+pub mod hover;
+pub mod include;
+pub mod index;
 pub mod ir;
 pub mod line_index;
+pub mod nav;
+pub mod outline;
+pub mod param;
+pub mod schema;
+pub mod template;
+pub mod transform;
 use core::fmt;
 use std::{
+    collections::BTreeMap,
     fs,
+    path::{Path, PathBuf},
     sync::{Arc, Mutex},
 };
 
-use ir::{Source, SourceProgram};
+use helper::{key, text_range};
+use index::Allow;
+use ir::{Config, Files, Keywords, Source};
 use line_index::LineIndex;
-use salsa::DebugWithDb;
+use salsa::Setter;
+use schema::Schema;
 use syntax::{
     dyna_nodes::SourceFile,
     parse::{parse_text, Parse},
 };
-use text_edit::TextEdit;
-use tower_lsp::lsp_types::{Diagnostic, Range};
-
-#[salsa::jar(db = Db)]
-pub struct Jar(
-    // input
-    crate::ir::Source,
-    crate::ir::Diff,
-    // struct
-    crate::ir::Card,
-    crate::ir::SourceProgram,
-    crate::ir::Diagnostics,
-    // fn
-    crate::ir::mat_54,
-    crate::ir::parse,
-    crate::ir::foo,
-    crate::ir::compile,
-);
-
-#[derive(Default)]
-#[salsa::db(crate::Jar)]
+use text_edit::{TextEdit, TextSize};
+use tower_lsp::lsp_types::TextDocumentContentChangeEvent;
+
+#[salsa::db]
+#[derive(Clone, Default)]
 pub struct RootDatabase {
     storage: salsa::Storage<Self>,
     pub cst: Option<(LineIndex, Parse<SourceFile>)>,
     logs: Option<Arc<Mutex<Vec<String>>>>,
+    keywords: Option<Keywords>,
+    config: Option<Config>,
+    files: Option<Files>,
+    /// decks read by `load`, fetched for along with the open files
+    loaded: Vec<Source>,
 }
 
 impl RootDatabase {
     pub fn new() -> RootDatabase {
-        RootDatabase::default()
+        let mut db = RootDatabase::default();
+        db.keywords = Some(Keywords::new(&db, Schema::builtin()));
+        db.config = Some(Config::new(&db, vec![]));
+        db.files = Some(Files::new(&db, BTreeMap::new()));
+        db
+    }
+
+    /// templates of `dirs` over the builtin ones, gives back the problems
+    pub fn load_keywords(&mut self, dirs: &[PathBuf]) -> Vec<String> {
+        let (schema, problems) = Schema::with_dirs(dirs);
+        let keywords = self.schema();
+        keywords.set_schema(self).to(schema);
+        problems
+    }
+
+    /// an open deck that `*INCLUDE`s it, the file itself if none does
+    pub fn main_of(&self, source: Source) -> Source {
+        let mut mains = self.open_sources().into_iter().filter(|&f| f != source);
+        mains.find(|&f| include::model(self, f).files().contains(&source)).unwrap_or(source)
+    }
+
+    /// the input of `path`, read from disk the first time. one file got to by
+    /// another name is the same input
+    pub fn input(&mut self, path: &str) -> Source {
+        let files = self.files();
+        if let Some(&source) = files.paths(self).get(path) {
+            return source;
+        }
+        let key = key(Path::new(path));
+        let source = match files.paths(self).get(&key) {
+            Some(&source) => source,
+            None => Source::new(self, key.clone(), None, None, fs::read_to_string(&key).ok()),
+        };
+        let mut paths = files.paths(self).clone();
+        paths.insert(key, source);
+        paths.insert(path.to_string(), source);
+        files.set_paths(self).to(paths);
+        source
+    }
+
+    /// a deck the editor doesn't have, read from disk with what it `*INCLUDE`s
+    pub fn load(&mut self, path: &str) -> Source {
+        let source = self.input(path);
+        if !self.loaded.contains(&source) {
+            self.loaded.push(source);
+        }
+        self.fetch();
+        source
+    }
+
+    /// reads the paths the models of the decks looked at and found nothing,
+    /// until there's nothing new. what's read may `*INCLUDE` more
+    fn fetch(&mut self) {
+        let mut mains = self.open_sources();
+        for &deck in &self.loaded {
+            if !mains.contains(&deck) {
+                mains.push(deck);
+            }
+        }
+        loop {
+            let missing: Vec<String> = mains.iter().flat_map(|&main| include::model(self, main).missing).collect();
+            let mut read = false;
+            for path in missing {
+                let Some(&source) = self.files().paths(self).get(&path) else {
+                    self.input(&path);
+                    read = true;
+                    continue;
+                };
+                let disk = fs::read_to_string(source.path(self)).ok();
+                if disk.is_some() {
+                    source.set_disk(self).to(disk);
+                    read = true;
+                }
+            }
+            if !read {
+                break;
+            }
+        }
+    }
+
+    /// the files the editor has open
+    fn open_sources(&self) -> Vec<Source> {
+        let mut open: Vec<Source> = self.files().paths(self).values().copied().filter(|f| f.text(self).is_some()).collect();
+        open.sort_by_key(|f| f.path(self).clone());
+        open.dedup();
+        open
+    }
+
+    /// definitions not to warn about when unused
+    pub fn allow_unused(&mut self, allow: Vec<Allow>) {
+        let config = self.config();
+        config.set_allow_unused(self).to(allow);
+    }
+
+    /// the editor opened it, its text wins over the disk
+    pub fn open(&mut self, path: &str, text: String) {
+        let source = self.input(path);
+        source.set_text(self).to(Some(text));
+        source.set_tree(self).to(None);
+        self.fetch();
+    }
+
+    /// `did_change` events, each on the text the one before left. a range not in
+    /// the text stops there, what came before is kept and the rest is an error
+    pub fn change(&mut self, path: &str, changes: Vec<TextDocumentContentChangeEvent>) -> anyhow::Result<()> {
+        let source = self.input(path);
+        let mut tree = ir::parse(self, source).node(self);
+        let mut text = match source.text(self) {
+            Some(text) => text.clone(),
+            None => tree.syntax_node().text().to_string(),
+        };
+        let mut result = Ok(());
+        for change in changes {
+            let Some(range) = change.range else {
+                tree = parse_text(&change.text);
+                text = change.text;
+                continue;
+            };
+            let end = TextSize::of(text.as_str());
+            let Some(at) = text_range(&LineIndex::new(&text), range).ok().filter(|r| r.end() <= end) else {
+                let (a, b) = (range.start, range.end);
+                let at = format!("{}:{}-{}:{}", a.line + 1, a.character + 1, b.line + 1, b.character + 1);
+                result = Err(anyhow::anyhow!("{path} is out of sync with the editor, no {at} in it"));
+                break;
+            };
+            let edit = TextEdit::replace(at, change.text);
+            for indel in &edit {
+                tree = tree.reparse(indel);
+            }
+            edit.apply(&mut text);
+        }
+        source.set_text(self).to(Some(text));
+        source.set_tree(self).to(Some(tree));
+        self.fetch();
+        result
+    }
+
+    /// paths of the files the editor has open
+    pub fn opened(&self) -> Vec<String> {
+        self.open_sources().into_iter().map(|f| f.path(self).clone()).collect()
+    }
+
+    /// the disk changed at `path`, read it again and look for the files that
+    /// weren't there before, an `*INCLUDE` may find one now
+    pub fn refresh(&mut self, path: &str) {
+        let files = self.files().paths(self).iter();
+        let mut stale: Vec<Source> = files.filter(|(p, f)| *p == path || f.disk(self).is_none()).map(|(_, f)| *f).collect();
+        stale.dedup();
+        for source in stale {
+            let disk = fs::read_to_string(source.path(self)).ok();
+            if *source.disk(self) != disk {
+                source.set_disk(self).to(disk);
+            }
+        }
+        self.fetch();
+    }
+
+    /// closed, back to what is on disk
+    pub fn close(&mut self, path: &str) {
+        let source = self.input(path);
+        source.set_text(self).to(None);
+        source.set_tree(self).to(None);
+        self.fetch();
     }
 }
 
@@ -53,15 +221,15 @@ impl fmt::Debug for RootDatabase {
     }
 }
 
+#[salsa::db]
 impl salsa::Database for RootDatabase {
-    fn salsa_event(&self, event: salsa::Event) {
+    fn salsa_event(&self, event: &dyn Fn() -> salsa::Event) {
         // Log interesting events, if logging is enabled
         if let Some(logs) = &self.logs {
             // don't log boring events
+            let event = event();
             if let salsa::EventKind::WillExecute { .. } = event.kind {
-                logs.lock()
-                    .unwrap()
-                    .push(format!("Event: {:?}", event.debug(self)));
+                logs.lock().unwrap().push(format!("Event: {event:?}"));
             }
         }
     }
@@ -76,12 +244,75 @@ impl salsa::Database for RootDatabase {
 //     }
 // }
 
-pub trait Db: salsa::DbWithJar<Jar> {
-    fn input(&self, path: &str) -> Source;
+#[salsa::db]
+pub trait Db: salsa::Database {
+    fn files(&self) -> Files;
+    fn schema(&self) -> Keywords;
+    fn config(&self) -> Config;
 }
 
+#[salsa::db]
 impl Db for RootDatabase {
-    fn input(&self, path: &str) -> Source {
-        Source::new(self, path.into())
+    fn files(&self) -> Files {
+        self.files.expect("RootDatabase::new sets files")
+    }
+
+    fn schema(&self) -> Keywords {
+        self.keywords.expect("RootDatabase::new sets keywords")
+    }
+
+    fn config(&self) -> Config {
+        self.config.expect("RootDatabase::new sets config")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use syntax::parse::parse_text;
+    use tower_lsp::lsp_types::{Position, Range, TextDocumentContentChangeEvent};
+
+    use crate::{ir::parse, RootDatabase};
+
+    fn change(from: (u32, u32), to: (u32, u32), text: &str) -> TextDocumentContentChangeEvent {
+        TextDocumentContentChangeEvent {
+            range: Some(Range::new(Position::new(from.0, from.1), Position::new(to.0, to.1))),
+            range_length: None,
+            text: text.to_string(),
+        }
+    }
+
+    fn text(db: &mut RootDatabase, path: &str) -> String {
+        let source = db.input(path);
+        let tree = parse(db, source).node(db);
+        let text = source.text(db).clone().unwrap();
+        // reparsed in place, same as parsing it all again
+        assert_eq!(tree.syntax_node().text().to_string(), text);
+        assert_eq!(tree.green, parse_text(&text).green);
+        text
+    }
+
+    #[test]
+    fn edits_in_a_row() {
+        let mut db = RootDatabase::new();
+        db.open("/deck.k", "*PART\npart\n         1\n".to_string());
+        let changes = vec![change((2, 9), (2, 10), "2"), change((1, 0), (1, 4), "bumper"), change((3, 0), (3, 0), "*END\n")];
+        db.change("/deck.k", changes).unwrap();
+        assert_eq!(text(&mut db, "/deck.k"), "*PART\nbumper\n         2\n*END\n");
+        let all = TextDocumentContentChangeEvent { range: None, range_length: None, text: "*NODE\n".to_string() };
+        db.change("/deck.k", vec![all, change((1, 0), (1, 0), "       1")]).unwrap();
+        assert_eq!(text(&mut db, "/deck.k"), "*NODE\n       1");
+    }
+
+    #[test]
+    fn an_edit_out_of_the_text_is_an_error() {
+        let mut db = RootDatabase::new();
+        db.open("/deck.k", "*PART\npart\n".to_string());
+        let changes = vec![change((1, 0), (1, 4), "head"), change((9, 0), (9, 1), "x"), change((0, 0), (0, 0), "$\n")];
+        assert!(db.change("/deck.k", changes).is_err());
+        // what came before the bad one stays
+        assert_eq!(text(&mut db, "/deck.k"), "*PART\nhead\n");
+        db.close("/deck.k");
+        let deck = db.input("/deck.k");
+        assert_eq!(deck.text(&db), &None);
     }
 }
@@ -24,7 +24,6 @@ impl LineIndex {
 
         let mut curr_row = 0.into();
         let mut curr_col: TextSize = 0.into();
-        let mut line = 0;
         for c in text.chars() {
             let c_len = TextSize::of(c);
             curr_row += c_len;
@@ -33,7 +32,6 @@ impl LineIndex {
 
                 // Prepare for processing the next line
                 curr_col = 0.into();
-                line += 1;
                 continue;
             }
 
@@ -0,0 +1,320 @@
+//! where the entity or parameter under the cursor is defined and used
+
+use syntax::parse::{TextRange, TextSize};
+
+use crate::{
+    index::index,
+    ir::{parse, Source},
+    param::params,
+    template::number,
+};
+
+/// where what's at `offset` of `source` is defined, in the model of `main`
+pub fn definition(db: &dyn crate::Db, main: Source, source: Source, offset: TextSize) -> Vec<(Source, TextRange)> {
+    // `&name` first, it may be in an ID field
+    let params = params(db, main);
+    if let Some((_, param)) = params.at(source, offset) {
+        let param = param.map(|p| &params.params[p]);
+        return param.map(|p| (p.file, p.range)).into_iter().collect();
+    }
+    let index = index(db, main);
+    let Some((entity, id, _)) = index.at(source, offset) else {return vec![]};
+    index.definitions_of(entity, id).map(|d| (d.file, d.range)).collect()
+}
+
+/// every place what's at `offset` is defined, `true`, or used
+pub fn references(db: &dyn crate::Db, main: Source, source: Source, offset: TextSize) -> Vec<(Source, TextRange, bool)> {
+    let params = params(db, main);
+    if let Some((_, param)) = params.at(source, offset) {
+        let Some(p) = param else {return vec![]};
+        let (name, scope) = (&params.params[p].name, params.params[p].scope);
+        let defs = params.params.iter().filter(|d| d.scope == scope && d.name.eq_ignore_ascii_case(name));
+        let uses = params.references.iter().filter(|r| r.param == Some(p));
+        let defs = defs.map(|d| (d.file, d.range, true));
+        return defs.chain(uses.map(|r| (r.file, r.range, false))).collect();
+    }
+    let index = index(db, main);
+    let Some((entity, id, _)) = index.at(source, offset) else {return vec![]};
+    let defs = index.definitions_of(entity, id).map(|d| (d.file, d.range, true));
+    defs.chain(index.uses_of(entity, id).map(|u| (u.file, u.range, false))).collect()
+}
+
+/// the ID at `offset` when it can be renamed, one written as a number
+pub fn renamable(db: &dyn crate::Db, main: Source, source: Source, offset: TextSize) -> Option<TextRange> {
+    let (_, _, range) = index(db, main).at(source, offset)?;
+    written(&text_at(db, source, range)).map(|_| range)
+}
+
+/// edits making the ID at `offset` `new`, as written there. the offsets of
+/// `*INCLUDE_TRANSFORM`s stay, every place moves by as much
+pub fn rename(
+    db: &dyn crate::Db,
+    main: Source,
+    source: Source,
+    offset: TextSize,
+    new: &str,
+) -> Result<Vec<(Source, TextRange, String)>, String> {
+    let index = index(db, main);
+    let Some((entity, id, range)) = index.at(source, offset) else {
+        return Err("no ID here".to_string());
+    };
+    let old = written(&text_at(db, source, range)).ok_or("the ID is not written as a number")?;
+    let new: i64 = new.trim().parse().ok().filter(|n| *n > 0).ok_or_else(|| format!("`{new}` is not an ID"))?;
+    let by = new - old.abs();
+    if by == 0 {
+        return Ok(vec![]);
+    }
+    if let Some(d) = index.definitions_of(entity, id + by).next() {
+        return Err(format!("{entity} {} is defined already, in {}", id + by, d.file.path(db)));
+    }
+    let defs = index.definitions_of(entity, id).map(|d| (d.file, d.range, d.slot, d.width));
+    let uses = index.uses_of(entity, id).map(|u| (u.file, u.range, u.slot, u.width));
+    let mut edits: Vec<(Source, TextRange, String)> = vec![];
+    for (file, range, slot, width) in defs.chain(uses) {
+        let Some(value) = written(&text_at(db, file, range)) else {
+            return Err(format!("{entity} {id} is given by a parameter in {}", file.path(db)));
+        };
+        // a negative curve ID keeps its sign
+        let value = match value < 0 {
+            true => value - by,
+            false => value + by,
+        };
+        let edit = fit(db, file, range, slot, width, value.to_string())?;
+        // a file included twice, each time read with another offset
+        match edits.iter().find(|(f, r, _)| *f == edit.0 && *r == edit.1) {
+            Some((_, _, text)) if *text != edit.2 => {
+                return Err(format!("{entity} {id} is written once for IDs that don't agree in {}", file.path(db)));
+            }
+            Some(_) => {}
+            None => edits.push(edit),
+        }
+    }
+    // a file included again with another offset reads the same text as
+    // another ID, that one would move too and what names it wouldn't
+    for (file, at, _) in &edits {
+        let mut ids = index.defined().chain(index.used());
+        let other = ids.find(|(e, i, f, r)| *e == entity && *i != id && f == file && at.contains_range(*r));
+        if let Some((_, other, _, _)) = other {
+            return Err(format!("{} is included more than once, {entity} {id} is {other} there too", file.path(db)));
+        }
+    }
+    Ok(edits)
+}
+
+/// `value` for the one at `range`, in the same column. `width` is the column's,
+/// the slot is as much of it as the line has
+fn fit(
+    db: &dyn crate::Db,
+    file: Source,
+    range: TextRange,
+    slot: Option<TextRange>,
+    width: usize,
+    value: String,
+) -> Result<(Source, TextRange, String), String> {
+    let Some(slot) = slot else {return Ok((file, range, value))};
+    if value.len() > width {
+        return Err(format!("{value} doesn't fit the {width} wide field in {}", file.path(db)));
+    }
+    // right-aligned, unless it was written from the left
+    let text = match range.start() == slot.start() && range.end() < slot.end() {
+        true => format!("{value:<len$}", len = usize::from(slot.len())),
+        false => format!("{value:>width$}"),
+    };
+    Ok((file, slot, text))
+}
+
+/// a whole number, `None` for a `&name`
+fn written(text: &str) -> Option<i64> {
+    let x = number(text)?;
+    (x.fract() == 0.).then_some(x as i64)
+}
+
+fn text_at(db: &dyn crate::Db, file: Source, range: TextRange) -> String {
+    parse(db, file).node(db).syntax_node().text().slice(range).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use syntax::parse::{TextRange, TextSize};
+
+    use super::{definition, references, renamable, rename};
+    use crate::{ir::Source, RootDatabase};
+
+    /// right-aligned in 10 columns each
+    fn line(values: &[&str]) -> String {
+        values.iter().map(|v| format!("{v:>10}")).collect::<String>() + "\n"
+    }
+
+    /// decks the editor has open, not on disk
+    fn open(files: &[(&str, String)]) -> RootDatabase {
+        let mut db = RootDatabase::new();
+        for (path, text) in files {
+            db.open(path, text.clone());
+        }
+        db
+    }
+
+    /// where `what` is in `file`, its `nth` one
+    fn at(db: &mut RootDatabase, file: &str, what: &str, nth: usize) -> (Source, TextSize) {
+        let source = db.input(file);
+        let text = source.text(db).clone().unwrap();
+        let i = text.match_indices(what).nth(nth).unwrap().0;
+        (source, TextSize::from(i as u32))
+    }
+
+    fn mat(id: &str) -> String {
+        format!("*MAT_ELASTIC\n{}", line(&[id, "7.8e-9", "210000.", "0.3"]))
+    }
+
+    /// `R name` and its value, one pair of columns
+    fn pair(name: &str, value: &str) -> String {
+        format!("{name:<10}{value:>10}")
+    }
+
+    /// a shell section with `t1` and `t2` given
+    fn shell(t1: &str, t2: &str) -> String {
+        format!("*SECTION_SHELL\n{}{}", line(&["1", "2"]), line(&[t1, t2]))
+    }
+
+    /// what the ranges are on, with whether it's a definition
+    fn texts(db: &RootDatabase, found: &[(Source, TextRange, bool)]) -> Vec<(String, bool)> {
+        let text = |f: &Source, r: &TextRange| f.text(db).clone().unwrap()[*r].to_string();
+        found.iter().map(|(f, r, def)| (text(f, r), *def)).collect()
+    }
+
+    #[test]
+    fn definition_and_references_across_includes() {
+        let main = format!("{}*PART\nbumper\n{}*INCLUDE\n/dbk/inc.k\n", mat("5"), line(&["1", "1", "5"]));
+        let inc = format!("*PART\nbeam\n{}", line(&["2", "1", "5"]));
+        let mut db = open(&[("/dbk/main.k", main), ("/dbk/inc.k", inc)]);
+        let (inc, offset) = at(&mut db, "/dbk/inc.k", "5", 0);
+        let main = db.input("/dbk/main.k");
+        let found = definition(&db, main, inc, offset);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].0, main);
+        let refs = references(&db, main, inc, offset);
+        let defs: Vec<_> = refs.iter().map(|(f, _, def)| (*f == main, *def)).collect();
+        assert_eq!(defs, vec![(true, true), (true, false), (false, false)]);
+    }
+
+    #[test]
+    fn rename_keeps_columns() {
+        let main = format!("{}*PART\nbumper\n{}", mat("5"), line(&["1", "1", "5"]));
+        let mut db = open(&[("/dbk/main.k", main)]);
+        let (main, offset) = at(&mut db, "/dbk/main.k", "5", 0);
+        assert!(renamable(&db, main, main, offset).is_some());
+        let edits = rename(&db, main, main, offset, "12").unwrap();
+        let texts: Vec<_> = edits.iter().map(|(_, _, t)| t.as_str()).collect();
+        assert_eq!(texts, vec!["        12", "        12"]);
+        assert!(edits.iter().all(|(_, r, _)| usize::from(r.len()) == 10));
+        let err = rename(&db, main, main, offset, "12345678901").unwrap_err();
+        assert!(err.starts_with("12345678901 doesn't fit the 10 wide field"), "{err}");
+        assert!(rename(&db, main, main, offset, "x").is_err());
+    }
+
+    #[test]
+    fn rename_where_the_line_stops_short() {
+        // `mid` is ` 5`, the line ends before its column does
+        let main = format!("{}*PART\nbumper\n         1         1 5\n", mat("5"));
+        let mut db = open(&[("/dbk/main.k", main)]);
+        let (main, offset) = at(&mut db, "/dbk/main.k", "5", 0);
+        let edits = rename(&db, main, main, offset, "1000").unwrap();
+        let texts: Vec<_> = edits.iter().map(|(_, _, t)| t.as_str()).collect();
+        assert_eq!(texts, vec!["      1000", "      1000"]);
+        assert_eq!(usize::from(edits[1].1.len()), 2);
+    }
+
+    #[test]
+    fn rename_refuses_collisions() {
+        let main = format!("{}{}", mat("5"), mat("6"));
+        let mut db = open(&[("/dbk/main.k", main)]);
+        let (main, offset) = at(&mut db, "/dbk/main.k", "5", 0);
+        let err = rename(&db, main, main, offset, "6").unwrap_err();
+        assert_eq!(err, "material 6 is defined already, in /dbk/main.k");
+        assert_eq!(rename(&db, main, main, offset, "5"), Ok(vec![]));
+    }
+
+    #[test]
+    fn a_file_included_twice() {
+        let inc = format!("*PART\nbeam\n{}", line(&["1", "1", "5"]));
+        let twice = format!("{}*INCLUDE\n/dbk/inc.k\n*INCLUDE_TRANSFORM\n/dbk/inc.k\n{}", mat("5"), line(&["0", "0", "1000"]));
+        let mut db = open(&[("/dbk/main.k", twice.clone()), ("/dbk/inc.k", inc.clone())]);
+        // one edit for the two reads of it
+        let (main, offset) = at(&mut db, "/dbk/main.k", "5", 0);
+        assert_eq!(rename(&db, main, main, offset, "7").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn a_file_included_under_two_offsets() {
+        let inc = format!("*PART\nbeam\n{}", line(&["1", "1", "5"]));
+        let twice = format!(
+            "{}{}*INCLUDE\n/dbk/inc.k\n*INCLUDE_TRANSFORM\n/dbk/inc.k\n{}",
+            mat("5"),
+            mat("105"),
+            line(&["0", "0", "1000", "100"]),
+        );
+        let mut db = open(&[("/dbk/main.k", twice), ("/dbk/inc.k", inc)]);
+        let main = db.input("/dbk/main.k");
+        // the part is 1 and 1001, the material it names 5 and 105
+        let (inc, offset) = at(&mut db, "/dbk/inc.k", "1", 0);
+        let err = rename(&db, main, inc, offset, "2").unwrap_err();
+        assert_eq!(err, "/dbk/inc.k is included more than once, part 1 is 1001 there too");
+        let (_, offset) = at(&mut db, "/dbk/main.k", "5", 0);
+        let err = rename(&db, main, main, offset, "7").unwrap_err();
+        assert_eq!(err, "/dbk/inc.k is included more than once, material 5 is 105 there too");
+    }
+
+    #[test]
+    fn definition_of_parameters() {
+        let main = format!(
+            "*PARAMETER\n{}\n*INCLUDE\n/dbk/inc.k\n{}",
+            pair("R   thick", "1.5"),
+            shell("&thick", "&none"),
+        );
+        let inc = format!("*PARAMETER_LOCAL\n{}\n{}", pair("R   thick", "2."), shell("&thick", ""));
+        let mut db = open(&[("/dbk/main.k", main), ("/dbk/inc.k", inc)]);
+        let main = db.input("/dbk/main.k");
+        let name = TextRange::at(TextSize::from(4), TextSize::from(5)) + TextSize::of("*PARAMETER\n");
+        let (source, offset) = at(&mut db, "/dbk/main.k", "&thick", 0);
+        assert_eq!(definition(&db, main, source, offset), vec![(main, name)]);
+        // the name itself, and the local one in the include
+        let (_, offset) = at(&mut db, "/dbk/main.k", "thick", 0);
+        assert_eq!(definition(&db, main, source, offset), vec![(main, name)]);
+        let (inc, offset) = at(&mut db, "/dbk/inc.k", "&thick", 0);
+        let local = TextRange::at(TextSize::from(4), TextSize::from(5)) + TextSize::of("*PARAMETER_LOCAL\n");
+        assert_eq!(definition(&db, main, inc, offset), vec![(inc, local)]);
+        let (_, offset) = at(&mut db, "/dbk/main.k", "&none", 0);
+        assert!(definition(&db, main, source, offset).is_empty());
+        // a part's material that isn't there, or nothing at all
+        let text = format!("*PART\nbumper\n{}", line(&["1", "1", "9"]));
+        let mut db = open(&[("/dbk/main.k", text)]);
+        let (main, offset) = at(&mut db, "/dbk/main.k", "9", 0);
+        assert!(definition(&db, main, main, offset).is_empty());
+        assert!(definition(&db, main, main, TextSize::from(2)).is_empty());
+    }
+
+    #[test]
+    fn references_of_parameters() {
+        let text = format!(
+            "*PARAMETER\n{}{}\n*PARAMETER_EXPRESSION\n{:<10}a*2\n{}",
+            pair("R       a", "1."),
+            pair("R       A", "2."),
+            "R       b",
+            shell("&a", "&b"),
+        );
+        let mut db = open(&[("/dbk/main.k", text)]);
+        let (main, offset) = at(&mut db, "/dbk/main.k", "&a", 0);
+        let found = references(&db, main, main, offset);
+        let expected = [("a", true), ("A", true), ("a", false), ("&a", false)];
+        assert_eq!(texts(&db, &found), expected.map(|(t, def)| (t.to_string(), def)));
+        // from the definition the same
+        let (_, offset) = at(&mut db, "/dbk/main.k", "b", 0);
+        assert_eq!(texts(&db, &references(&db, main, main, offset)).len(), 2);
+        // an entity, defined and used
+        let text = format!("{}*PART\nbumper\n{}", mat("5"), line(&["1", "1", "5"]));
+        let mut db = open(&[("/dbk/main.k", text)]);
+        let (main, offset) = at(&mut db, "/dbk/main.k", "5", 1);
+        let found = references(&db, main, main, offset);
+        assert_eq!(texts(&db, &found), vec![("5".to_string(), true), ("5".to_string(), false)]);
+    }
+}
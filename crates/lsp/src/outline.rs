@@ -0,0 +1,188 @@
+//! the outline of a file: keyword families, their cards by ID and title
+
+use syntax::{ast::AstNode, parse::TextRange};
+use tower_lsp::lsp_types::{DocumentSymbol, SymbolKind};
+
+use crate::{
+    helper::range,
+    index::{index, Definition, Mesh},
+    ir::{parse, Source},
+    line_index::LineIndex,
+};
+
+/// `MAT`, `PART` .. each with its cards, cards of a family one after the
+/// other are one, the same family further down is another
+pub fn outline(db: &dyn crate::Db, source: Source) -> Vec<DocumentSymbol> {
+    let program = parse(db, source);
+    let lines = program.lines(db);
+    // as written, the file on its own
+    let index = index(db, source);
+    // in the order of the text, read along with the cards
+    let mut defs: Vec<_> = index.definitions.iter().filter(|d| d.file == source).collect();
+    defs.sort_by_key(|d| d.range.start());
+    let mut defs = defs.into_iter().peekable();
+    let mut mesh = index.mesh.iter().filter(|m| m.file == source).peekable();
+    let mut families: Vec<(String, TextRange, Vec<DocumentSymbol>)> = vec![];
+    for card in program.node(db).tree().cards() {
+        let Some(kw) = card.keyword() else {continue};
+        let name = kw.name();
+        let family = name.base().split('_').next().unwrap_or_default().to_string();
+        let at = card.syntax().text_range();
+        let mut own = vec![];
+        while let Some(d) = defs.next_if(|d| d.range.start() < at.end()) {
+            own.extend(Some(d).filter(|d| at.contains_range(d.range)));
+        }
+        let mut card_mesh = None;
+        while let Some(m) = mesh.next_if(|m| m.range.start() < at.end()) {
+            card_mesh = card_mesh.or(Some(m).filter(|m| m.range == at));
+        }
+        let symbol = card_symbol(lines, &format!("*{name}"), at, kw.syntax().text_range(), &own, card_mesh);
+        match families.last_mut() {
+            Some((f, range, cards)) if *f == family => {
+                *range = range.cover(at);
+                cards.push(symbol);
+            }
+            _ => families.push((family, at, vec![symbol])),
+        }
+    }
+    families
+        .into_iter()
+        .map(|(family, at, cards)| symbol(lines, family, None, SymbolKind::NAMESPACE, at, at, Some(cards)))
+        .collect()
+}
+
+/// named by the ID it defines and its title, nodes and elements counted
+fn card_symbol(
+    lines: &LineIndex,
+    keyword: &str,
+    at: TextRange,
+    kw: TextRange,
+    defs: &[&Definition],
+    mesh: Option<&Mesh>,
+) -> DocumentSymbol {
+    let detail = Some(keyword.to_string());
+    let name = |d: &Definition| match &d.title {
+        Some(title) => format!("{} {title}", d.id),
+        None => d.id.to_string(),
+    };
+    let mut meshed = mesh.into_iter().flat_map(|m| &m.ids).filter(|i| i.defines);
+    if let Some(first) = meshed.next() {
+        let count = match meshed.count() + 1 {
+            1 => format!("1 {}", first.entity),
+            n => format!("{n} {}s", first.entity),
+        };
+        return symbol(lines, keyword.to_string(), Some(count), SymbolKind::ARRAY, at, kw, None);
+    }
+    match defs {
+        [] => symbol(lines, keyword.to_string(), None, SymbolKind::PROPERTY, at, kw, None),
+        [d] => symbol(lines, name(d), detail, SymbolKind::OBJECT, at, kw, None),
+        // `*PART` with many parts, one each
+        [d, ..] => {
+            let own = defs.iter().copied().filter(|o| o.entity == d.entity);
+            let children = own.map(|d| symbol(lines, name(d), detail.clone(), SymbolKind::OBJECT, d.range, d.range, None));
+            symbol(lines, keyword.to_string(), None, SymbolKind::PROPERTY, at, kw, Some(children.collect()))
+        }
+    }
+}
+
+#[allow(deprecated)]
+fn symbol(
+    lines: &LineIndex,
+    name: String,
+    detail: Option<String>,
+    kind: SymbolKind,
+    at: TextRange,
+    selection: TextRange,
+    children: Option<Vec<DocumentSymbol>>,
+) -> DocumentSymbol {
+    DocumentSymbol {
+        name,
+        detail,
+        kind,
+        tags: None,
+        deprecated: None,
+        range: range(lines, at),
+        selection_range: range(lines, selection),
+        children,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tower_lsp::lsp_types::{DocumentSymbol, SymbolKind};
+
+    use super::outline;
+    use crate::RootDatabase;
+
+    /// right-aligned in 10 columns each
+    fn line(values: &[&str]) -> String {
+        values.iter().map(|v| format!("{v:>10}")).collect::<String>() + "\n"
+    }
+
+    /// name, detail and kind, children indented under
+    fn tree(symbols: &[DocumentSymbol], depth: usize, out: &mut Vec<String>) {
+        for s in symbols {
+            let detail = s.detail.as_deref().unwrap_or("-");
+            let kind = match s.kind {
+                SymbolKind::NAMESPACE => "family",
+                SymbolKind::PROPERTY => "card",
+                SymbolKind::OBJECT => "id",
+                SymbolKind::ARRAY => "mesh",
+                _ => "?",
+            };
+            out.push(format!("{}{} | {detail} | {kind}", "  ".repeat(depth), s.name));
+            tree(s.children.as_deref().unwrap_or_default(), depth + 1, out);
+        }
+    }
+
+    #[test]
+    fn families_cards_and_ids() {
+        let mat = |id: &str| line(&[id, "7.8e-9", "210000.", "0.3"]);
+        let node = |id: usize| format!("{id:>8}{:>16}{:>16}{:>16}\n", "0.0", "0.0", "0.0");
+        let text = format!(
+            "*MAT_ELASTIC_TITLE\nsteel\n{}*PART\nbumper\n{}beam\n{}*MAT_ELASTIC\n{}*SECTION_SHELL\n{}{}*CONTROL_TERMINATION\n{}*NODE\n{}{}*ELEMENT_SHELL\n{:>8}{:>8}{:>8}\n",
+            mat("5"),
+            line(&["1", "1", "5"]),
+            line(&["2", "1", "6"]),
+            mat("6"),
+            line(&["1", "2"]),
+            line(&["1.0"]),
+            line(&["0.1"]),
+            node(1),
+            node(2),
+            1,
+            1,
+            1,
+        );
+        let mut db = RootDatabase::new();
+        db.open("/dbk/main.k", text);
+        let main = db.input("/dbk/main.k");
+        let symbols = outline(&db, main);
+        let mut found = vec![];
+        tree(&symbols, 0, &mut found);
+        assert_eq!(found, vec![
+            "MAT | - | family",
+            "  5 steel | *MAT_ELASTIC_TITLE | id",
+            "PART | - | family",
+            "  *PART | - | card",
+            "    1 bumper | *PART | id",
+            "    2 beam | *PART | id",
+            "MAT | - | family",
+            "  6 | *MAT_ELASTIC | id",
+            "SECTION | - | family",
+            "  1 | *SECTION_SHELL | id",
+            "CONTROL | - | family",
+            "  *CONTROL_TERMINATION | - | card",
+            "NODE | - | family",
+            "  *NODE | 2 nodes | mesh",
+            "ELEMENT | - | family",
+            "  *ELEMENT_SHELL | 1 element | mesh",
+        ]);
+        // a family covers its cards and no other's, a card is picked by its keyword
+        let ends: Vec<_> = symbols.iter().map(|s| (s.range.start.line, s.range.end.line)).collect();
+        assert!(ends.windows(2).all(|w| w[0].1 <= w[1].0), "{ends:?}");
+        assert_eq!(ends[1], (3, 8));
+        let card = &symbols[2].children.as_ref().unwrap()[0];
+        assert_eq!((card.selection_range.start.line, card.range.start.line), (8, 8));
+    }
+}
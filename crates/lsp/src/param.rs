@@ -0,0 +1,419 @@
+//! `*PARAMETER`s of the model, what they are worth and every `&name` reading them
+
+use std::{
+    collections::{hash_map, HashMap},
+    fmt,
+};
+
+use syntax::{
+    ast::AstNode,
+    dyna_nodes::{Card, Field, Record},
+    keyword::KwOption,
+    parse::{TextRange, TextSize},
+    syntax_node::SyntaxToken,
+};
+
+use crate::{
+    expr::Expr,
+    include::{model, Entry, Model},
+    ir::Source,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Int(i64),
+    Real(f64),
+    Char(String),
+}
+
+// `Expr::eval` errs on what isn't finite, a NaN never gets in
+impl Eq for Value {}
+
+impl Value {
+    pub fn number(&self) -> Option<f64> {
+        match self {
+            Value::Int(i) => Some(*i as f64),
+            Value::Real(x) => Some(*x),
+            Value::Char(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Int(i) => write!(f, "{i}"),
+            Value::Real(x) => write!(f, "{x:?}"),
+            Value::Char(s) => write!(f, "\"{s}\""),
+        }
+    }
+}
+
+/// one definition, taken or not
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Param {
+    pub name: String,
+    /// `R`, `I` or `C`
+    pub kind: char,
+    /// `None` when it can't be worked out
+    pub value: Option<Value>,
+    pub file: Source,
+    /// of the name
+    pub range: TextRange,
+    /// the `*INCLUDE` a `_LOCAL` one is seen under, `None` is everywhere
+    pub scope: Option<usize>,
+    /// of `*PARAMETER_EXPRESSION`
+    pub expression: Option<String>,
+}
+
+/// a `&name` in a field or a name in an expression
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Reference {
+    pub file: Source,
+    pub range: TextRange,
+    /// in `Params::params`, `None` when it's not defined
+    pub param: Option<usize>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Params {
+    pub params: Vec<Param>,
+    pub references: Vec<Reference>,
+    /// undefined and duplicated ones, bad expressions
+    pub problems: Vec<(Source, TextRange, String)>,
+}
+
+impl Params {
+    /// the parameter defined or read at `offset`, and where
+    pub fn at(&self, file: Source, offset: TextSize) -> Option<(TextRange, Option<usize>)> {
+        let defs = self.params.iter().enumerate().map(|(i, p)| (p.file, p.range, Some(i)));
+        let refs = self.references.iter().map(|r| (r.file, r.range, r.param));
+        refs.chain(defs)
+            .find(|(f, range, _)| *f == file && range.contains_inclusive(offset))
+            .map(|(_, range, param)| (range, param))
+    }
+}
+
+#[salsa::tracked]
+pub fn params(db: &dyn crate::Db, main: Source) -> Params {
+    let model = model(db, main);
+    let mut walk = Walk {
+        model: &model,
+        taken: HashMap::new(),
+        dflag: 1,
+        params: Params::default(),
+        later: vec![],
+    };
+    for (entry, card) in model.cards(db) {
+        walk.card(&entry, &card);
+    }
+    // `&name` in the cards may come before the `*PARAMETER`
+    for (entry, token) in std::mem::take(&mut walk.later) {
+        let chain = model.include_chain(&entry);
+        walk.reference(entry.file, &token.text()[1..], token.text_range(), &chain);
+    }
+    walk.params
+}
+
+struct Walk<'a> {
+    model: &'a Model,
+    /// the definition that counts, by lowercase name and scope
+    taken: HashMap<(String, Option<usize>), usize>,
+    /// of `*PARAMETER_DUPLICATION`
+    dflag: i64,
+    params: Params,
+    /// `&name`s in the other cards
+    later: Vec<(Entry, SyntaxToken)>,
+}
+
+impl Walk<'_> {
+    fn card(&mut self, entry: &Entry, card: &Card) {
+        let Some(kw) = card.keyword() else {return};
+        let name = kw.name();
+        let scope = match name.has(KwOption::Local) {
+            true => entry.include,
+            false => None,
+        };
+        match name.base() {
+            "PARAMETER" => {
+                for record in card.records() {
+                    let fields: Vec<_> = record.fields().collect();
+                    for pair in fields.chunks(2) {
+                        self.parameter(entry, &pair[0], pair.get(1), scope);
+                    }
+                }
+            }
+            "PARAMETER_EXPRESSION" => {
+                for record in card.records() {
+                    self.expression(entry, &record, scope);
+                }
+            }
+            "PARAMETER_DUPLICATION" => {
+                let dflag = card.records().next().and_then(|r| r.field(0)).and_then(|f| f.value());
+                self.dflag = dflag.and_then(|d| d.parse().ok()).filter(|d| (1..=4).contains(d)).unwrap_or(1);
+            }
+            _ => {
+                let params = card.records().flat_map(|r| r.fields()).filter_map(|f| f.param());
+                self.later.extend(params.map(|t| (*entry, t)));
+            }
+        }
+    }
+
+    /// `PRMR` and `VAL` of `*PARAMETER`
+    fn parameter(&mut self, entry: &Entry, prmr: &Field, val: Option<&Field>, scope: Option<usize>) {
+        let Some((kind, name, range)) = self.name(entry.file, prmr) else {return};
+        let value = match (kind, val.and_then(|v| v.value())) {
+            ('C', v) => Some(Value::Char(v.unwrap_or_default())),
+            (_, None) => Some(number(kind, 0.)),
+            (_, Some(text)) => {
+                let at = val.map_or(range.end(), |v| value_range(v).start());
+                self.eval(entry, &text, at).map(|x| number(kind, x))
+            }
+        };
+        self.define(Param { name, kind, value, file: entry.file, range, scope, expression: None });
+    }
+
+    /// `PRMR` then the expression to the end of the line
+    fn expression(&mut self, entry: &Entry, record: &Record, scope: Option<usize>) {
+        let Some(prmr) = record.field(0) else {return};
+        let Some((kind, name, range)) = self.name(entry.file, &prmr) else {return};
+        let line = record.line_range();
+        let start = record.syntax().text_range().start();
+        let text = record.syntax().text().to_string();
+        let mut at = prmr.syntax().text_range().end();
+        let mut rest = &text[TextRange::new(at, line.end()) - start];
+        if let Some(r) = rest.strip_prefix(',') {
+            (rest, at) = (r, at + TextSize::of(','));
+        }
+        let expression = rest.trim();
+        at += TextSize::of(&rest[..rest.len() - rest.trim_start().len()]);
+        let value = match kind {
+            'C' => Some(Value::Char(expression.to_string())),
+            _ => self.eval(entry, expression, at).map(|x| number(kind, x)),
+        };
+        let expression = Some(expression.to_string());
+        self.define(Param { name, kind, value, file: entry.file, range, scope, expression });
+    }
+
+    /// `R name` or `Rname`, `I` and `C` alike
+    fn name(&mut self, file: Source, field: &Field) -> Option<(char, String, TextRange)> {
+        let text = field.value()?;
+        let range = value_range(field);
+        let first = text.chars().next()?;
+        let name = text[first.len_utf8()..].trim_start();
+        let kind = first.to_ascii_uppercase();
+        if !matches!(kind, 'R' | 'I' | 'C') || name.is_empty() || name.contains(' ') {
+            let msg = format!("`{text}` should be `R`, `I` or `C` and a name");
+            self.params.problems.push((file, range, msg));
+            return None;
+        }
+        let range = TextRange::new(range.end() - TextSize::of(name), range.end());
+        Some((kind, name.to_string(), range))
+    }
+
+    /// the expression at `at`, its names are references
+    fn eval(&mut self, entry: &Entry, text: &str, at: TextSize) -> Option<f64> {
+        let expr = match Expr::parse(text) {
+            Ok(e) => e,
+            Err(msg) => {
+                let range = TextRange::at(at, TextSize::of(text));
+                self.params.problems.push((entry.file, range, msg));
+                return None;
+            }
+        };
+        let chain = self.model.include_chain(entry);
+        let mut defined = true;
+        for (name, range) in expr.names() {
+            defined &= self.reference(entry.file, name, range + at, &chain).is_some();
+        }
+        if !defined {
+            return None;
+        }
+        let params = &self.params.params;
+        let taken = &self.taken;
+        let mut value = |name: &str| {
+            let p = lookup(taken, name, &chain)?;
+            params[p].value.as_ref()?.number()
+        };
+        match expr.eval(&mut value) {
+            Ok(x) => Some(x),
+            Err(msg) => {
+                let range = TextRange::at(at, TextSize::of(text));
+                self.params.problems.push((entry.file, range, msg));
+                None
+            }
+        }
+    }
+
+    fn reference(&mut self, file: Source, name: &str, range: TextRange, chain: &[usize]) -> Option<usize> {
+        let param = lookup(&self.taken, name, chain);
+        if param.is_none() {
+            self.params.problems.push((file, range, format!("parameter `{name}` is not defined")));
+        }
+        self.params.references.push(Reference { file, range, param });
+        param
+    }
+
+    /// as `*PARAMETER_DUPLICATION` says when it's there already
+    fn define(&mut self, param: Param) {
+        let key = (param.name.to_ascii_lowercase(), param.scope);
+        let (file, range, name) = (param.file, param.range, param.name.clone());
+        let at = self.params.params.len();
+        self.params.params.push(param);
+        match self.taken.entry(key) {
+            hash_map::Entry::Vacant(e) => {
+                e.insert(at);
+                return;
+            }
+            // 1 and 3 keep the first, 1 and 2 tell
+            hash_map::Entry::Occupied(mut e) if matches!(self.dflag, 2 | 4) => {
+                e.insert(at);
+            }
+            hash_map::Entry::Occupied(_) => {}
+        }
+        let msg = match self.dflag {
+            1 => format!("`{name}` is defined again, the first one is kept"),
+            2 => format!("`{name}` is defined again, this one is taken"),
+            _ => return,
+        };
+        self.params.problems.push((file, range, msg));
+    }
+}
+
+/// innermost `_LOCAL` first, then the global one
+fn lookup(taken: &HashMap<(String, Option<usize>), usize>, name: &str, chain: &[usize]) -> Option<usize> {
+    let name = name.to_ascii_lowercase();
+    let mut scopes = chain.iter().map(|&i| Some(i)).chain([None]);
+    scopes.find_map(|s| taken.get(&(name.clone(), s)).copied())
+}
+
+/// `I` ones are truncated
+fn number(kind: char, x: f64) -> Value {
+    match kind {
+        'I' => Value::Int(x.trunc() as i64),
+        _ => Value::Real(x),
+    }
+}
+
+/// of the value without the blanks around
+fn value_range(field: &Field) -> TextRange {
+    let text = field.syntax().text().to_string();
+    let lead = TextSize::of(&text[..text.len() - text.trim_start().len()]);
+    TextRange::at(field.syntax().text_range().start() + lead, TextSize::of(text.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use syntax::parse::TextSize;
+
+    use super::{params, Value};
+    use crate::{RootDatabase};
+
+    /// `R name` and its value, one pair of columns
+    fn pair(name: &str, value: &str) -> String {
+        format!("{name:<10}{value:>10}")
+    }
+
+    /// a shell section with `t1` given by `value`
+    fn shell(value: &str) -> String {
+        format!("*SECTION_SHELL\n{:>10}{:>10}\n{value:>10}\n", 1, 2)
+    }
+
+    fn open(files: &[(&str, String)]) -> RootDatabase {
+        let mut db = RootDatabase::new();
+        for (path, text) in files {
+            db.open(path, text.clone());
+        }
+        db
+    }
+
+    fn messages(db: &mut RootDatabase, main: &str) -> Vec<String> {
+        let main = db.input(main);
+        params(db, main).problems.into_iter().map(|(_, _, msg)| msg).collect()
+    }
+
+    #[test]
+    fn values_and_references() {
+        let text = format!(
+            "*PARAMETER\n{}{}\n*PARAMETER_EXPRESSION\n{:<10}2*thick+n\n{}",
+            pair("R   thick", "1.5"),
+            pair("I      n", "3.7"),
+            "R   double",
+            shell("&double"),
+        );
+        let mut db = open(&[("/dbk/main.k", text.clone())]);
+        let main = db.input("/dbk/main.k");
+        let params = params(&db, main);
+        assert!(params.problems.is_empty(), "{:?}", params.problems);
+        let values: Vec<_> = params.params.iter().map(|p| (p.name.as_str(), p.value.clone())).collect();
+        assert_eq!(values, vec![
+            ("thick", Some(Value::Real(1.5))),
+            ("n", Some(Value::Int(3))),
+            ("double", Some(Value::Real(6.))),
+        ]);
+        assert_eq!(params.params[2].expression.as_deref(), Some("2*thick+n"));
+        // `thick` and `n` in the expression, `&double` in the section
+        let read: Vec<_> = params.references.iter().map(|r| (&text[r.range], r.param)).collect();
+        assert_eq!(read, vec![("thick", Some(0)), ("n", Some(1)), ("&double", Some(2))]);
+        let at = TextSize::from(text.find("&double").unwrap() as u32 + 1);
+        assert_eq!(params.at(main, at).map(|(_, p)| p), Some(Some(2)));
+        let at = TextSize::from(text.find("thick").unwrap() as u32);
+        assert_eq!(params.at(main, at).map(|(_, p)| p), Some(Some(0)));
+    }
+
+    #[test]
+    fn undefined_and_defined_twice() {
+        let twice = format!("{}{}", pair("R       a", "1."), pair("R       A", "2."));
+        let text = format!("*PARAMETER\n{twice}\n{}", shell("&b"));
+        let mut db = open(&[("/dbk/main.k", text)]);
+        let main = db.input("/dbk/main.k");
+        let kept = "`A` is defined again, the first one is kept";
+        assert_eq!(messages(&mut db, "/dbk/main.k"), vec![kept, "parameter `b` is not defined"]);
+        assert_eq!(params(&db, main).references[0].param, None);
+        // the last one wins, and nothing is said
+        let text = format!("*PARAMETER_DUPLICATION\n{:>10}\n*PARAMETER\n{twice}\n{}", 4, shell("&a"));
+        let mut db = open(&[("/dbk/main.k", text)]);
+        let main = db.input("/dbk/main.k");
+        let params = params(&db, main);
+        assert!(params.problems.is_empty());
+        assert_eq!(params.references[0].param, Some(1));
+    }
+
+    #[test]
+    fn local_to_the_include() {
+        let main = format!("*PARAMETER\n{}\n*INCLUDE\n/dbk/inc.k\n{}", pair("R       a", "1."), shell("&a"));
+        let inc = format!("*PARAMETER_LOCAL\n{}\n{}", pair("R       a", "2."), shell("&a"));
+        let mut db = open(&[("/dbk/main.k", main), ("/dbk/inc.k", inc)]);
+        let (main, inc) = (db.input("/dbk/main.k"), db.input("/dbk/inc.k"));
+        let params = params(&db, main);
+        assert!(params.problems.is_empty(), "{:?}", params.problems);
+        assert_eq!(params.params[1].scope, Some(0));
+        let read: Vec<_> = params.references.iter().map(|r| (r.file == inc, r.param)).collect();
+        assert_eq!(read, vec![(true, Some(1)), (false, Some(0))]);
+    }
+
+    #[test]
+    fn bad_names_and_expressions() {
+        let text = format!(
+            "*PARAMETER\n{}\n{}\n*PARAMETER_EXPRESSION\n{:<10}1/0\n{:<10}2*(c\n{:<10}sqrt(-1)\n",
+            pair("X       a", "1."),
+            pair("R       e", "1e999"),
+            "R       c",
+            "R       d",
+            "R       f",
+        );
+        let mut db = open(&[("/dbk/main.k", text)]);
+        let expected = vec![
+            "`X       a` should be `R`, `I` or `C` and a name",
+            "`1e999` is not a finite number",
+            "division by zero",
+            "`(` is not closed",
+            "the result is not a finite number",
+        ];
+        assert_eq!(messages(&mut db, "/dbk/main.k"), expected);
+        let main = db.input("/dbk/main.k");
+        let params = params(&db, main);
+        let values: Vec<_> = params.params.iter().map(|p| (p.name.as_str(), p.value.clone())).collect();
+        assert_eq!(values, vec![("e", None), ("c", None), ("d", None), ("f", None)]);
+    }
+}
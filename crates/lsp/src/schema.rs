@@ -0,0 +1,209 @@
+//! what each keyword looks like, card by card, field by field.
+//! built from `keywords/*.k` by `build.rs`.
+
+use std::{
+    collections::HashMap,
+    env,
+    path::{Path, PathBuf},
+};
+
+use syntax::keyword::{KeywordName, KwOption};
+
+use crate::template;
+pub use crate::template::{
+    CardSchema, Choice, Condition, Entity, FieldSchema, Kind, KeywordSchema, Range,
+};
+
+static BUILTIN: &str = include_str!(concat!(env!("OUT_DIR"), "/keywords.json"));
+
+/// more template dirs, separated like `PATH`
+pub const KEYWORDS_ENV: &str = "DBK_KEYWORDS";
+
+/// dirs listed in `DBK_KEYWORDS`
+pub fn env_dirs() -> Vec<PathBuf> {
+    env::var_os(KEYWORDS_ENV).map_or(vec![], |v| env::split_paths(&v).collect())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schema {
+    /// sorted by name
+    keywords: Vec<KeywordSchema>,
+}
+
+impl Schema {
+    /// the templates shipped with the binary
+    pub fn builtin() -> Schema {
+        let keywords = serde_json::from_str(BUILTIN).expect("keywords.json from build.rs");
+        Schema { keywords }
+    }
+
+    /// builtin templates with the ones of `dirs` on top, later dirs win.
+    /// gives back what went wrong, a keyword two dirs both have is reported
+    pub fn with_dirs(dirs: &[PathBuf]) -> (Schema, Vec<String>) {
+        let mut schema = Schema::builtin();
+        let mut problems = vec![];
+        // keyword and the dir it was taken from
+        let mut from: HashMap<String, &Path> = HashMap::new();
+        for dir in dirs {
+            let keywords = match template::read_templates(dir) {
+                Ok(k) => k,
+                Err(e) => {
+                    problems.push(format!("{}: {e}", dir.display()));
+                    continue;
+                }
+            };
+            // `*MAT_ELASTIC` of the user does for the builtin `*MAT_ELASTIC_TITLE` too
+            schema.keywords.retain(|k| {
+                let name = k.keyword_name();
+                from.contains_key(&k.name)
+                    || !keywords.iter().any(|u| u.keyword_name().same_family(&name))
+            });
+            for kw in keywords {
+                if let Some(prev) = from.insert(kw.name.clone(), dir) {
+                    problems.push(format!(
+                        "*{} of {} overrides the one of {}",
+                        kw.name,
+                        dir.display(),
+                        prev.display()
+                    ));
+                }
+                match schema.keywords.binary_search_by(|k| k.name.cmp(&kw.name)) {
+                    Ok(i) => schema.keywords[i] = kw,
+                    Err(i) => schema.keywords.insert(i, kw),
+                }
+            }
+            if let Err(e) = template::read_specs(dir, &mut schema.keywords) {
+                problems.push(e.to_string());
+            }
+        }
+        (schema, problems)
+    }
+
+    pub fn keywords(&self) -> &[KeywordSchema] {
+        &self.keywords
+    }
+
+    /// the template of that name, or one of the same family with the
+    /// `_TITLE`/`_ID` card added or taken away
+    pub fn get(&self, name: &KeywordName) -> Option<KeywordSchema> {
+        if let Some(kw) = self.keywords.iter().find(|k| k.keyword_name().same_as(name)) {
+            return Some(kw.clone());
+        }
+        let wanted = name.variant();
+        // same base, as many of the other options as there is a template for
+        let family = self
+            .keywords
+            .iter()
+            .map(|k| (k, k.keyword_name()))
+            .filter(|(_, n)| n.base() == name.base())
+            .filter(|(_, n)| n.variant().iter().all(|o| wanted.contains(o)))
+            .max_by_key(|(_, n)| n.variant().len());
+        let (kw, kw_name) = family?;
+        let mut kw = kw.clone();
+        let lead = [
+            (KwOption::Id, CardSchema::is_id as fn(&CardSchema) -> bool, id_card()),
+            (KwOption::Title, CardSchema::is_title, title_card()),
+        ];
+        // the card of one option taken away before the other's goes in front of it
+        for (option, is_card, _) in &lead {
+            if !name.has(*option) && kw_name.has(*option) && kw.cards.first().is_some_and(is_card) {
+                kw.cards.remove(0);
+            }
+        }
+        for (option, is_card, card) in lead {
+            if name.has(option) && !kw_name.has(option) && !kw.cards.first().is_some_and(is_card) {
+                kw.cards.insert(0, card);
+            }
+        }
+        kw.name = name.to_string();
+        Some(kw)
+    }
+}
+
+fn title_card() -> CardSchema {
+    CardSchema::new(vec![text_field("title", 0)])
+}
+
+fn id_card() -> CardSchema {
+    let id = FieldSchema {
+        required: true,
+        kind: Some(Kind::Integer),
+        ..FieldSchema::new("id", 0, 10)
+    };
+    CardSchema::new(vec![id, text_field("heading", 10)])
+}
+
+fn text_field(name: &str, start: usize) -> FieldSchema {
+    FieldSchema::new(name, start, 80 - start)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use syntax::keyword::KeywordName;
+    use tempfile::tempdir;
+
+    use super::Schema;
+
+    #[test]
+    fn builtin_templates() {
+        let schema = Schema::builtin();
+        let names: Vec<_> = schema.keywords().iter().map(|k| k.name.as_str()).collect();
+        assert!(names.windows(2).all(|w| w[0] < w[1]));
+        assert!(names.contains(&"SECTION_SHELL_TITLE") && names.contains(&"MAT_ELASTIC_TITLE"));
+        // the template has the title card, the plain keyword doesn't
+        let shell = schema.get(&KeywordName::parse("SECTION_SHELL")).unwrap();
+        assert_eq!(shell.name, "SECTION_SHELL");
+        assert_eq!(shell.cards[0].fields[0].name, "secid");
+    }
+
+    #[test]
+    fn title_and_id_cards_added_or_taken_away() {
+        let schema = Schema::builtin();
+        let plain = schema.get(&KeywordName::parse("MAT_ELASTIC")).unwrap();
+        let title = schema.get(&KeywordName::parse("MAT_ELASTIC_TITLE")).unwrap();
+        assert_eq!(title.name, "MAT_ELASTIC_TITLE");
+        assert!(title.cards[0].is_title());
+        assert_eq!(title.cards[1..], plain.cards[..]);
+        let id = schema.get(&KeywordName::parse("SECTION_SHELL_ID")).unwrap();
+        assert!(id.cards[0].is_id());
+        assert_eq!(id.cards[1].fields[0].name, "secid");
+        assert!(schema.get(&KeywordName::parse("NO_SUCH_KEYWORD")).is_none());
+    }
+
+    #[test]
+    fn dirs_on_top_of_the_builtin_ones() {
+        let tmp = tempdir().unwrap();
+        let user = tmp.path().to_path_buf();
+        fs::write(user.join("section_shell.k"), "*SECTION_SHELL\n$#   secid     thick\n         ?       1.0\n").unwrap();
+        fs::write(user.join("section_shell.spec"), "*SECTION_SHELL\nabout mine\nthick F (0,)\n").unwrap();
+        let (schema, problems) = Schema::with_dirs(&[user]);
+        assert!(problems.is_empty(), "{problems:?}");
+        // the builtin `_TITLE` one is gone, the family is the user's
+        let names: Vec<_> = schema.keywords().iter().map(|k| k.name.as_str()).collect();
+        assert!(names.contains(&"SECTION_SHELL") && !names.contains(&"SECTION_SHELL_TITLE"));
+        let title = schema.get(&KeywordName::parse("SECTION_SHELL_TITLE")).unwrap();
+        assert!(title.cards[0].is_title());
+        assert_eq!(title.cards[1].fields[1].name, "thick");
+        assert_eq!(title.summary.as_deref(), Some("mine"));
+        assert!(schema.get(&KeywordName::parse("MAT_ELASTIC")).is_some());
+    }
+
+    #[test]
+    fn what_went_wrong_with_dirs() {
+        let tmp = (tempdir().unwrap(), tempdir().unwrap());
+        let (a, b) = (tmp.0.path().to_path_buf(), tmp.1.path().to_path_buf());
+        for d in [&a, &b] {
+            fs::write(d.join("thing.k"), "*THING\n$#       x\n         ?\n").unwrap();
+        }
+        fs::write(b.join("thing.spec"), "*THING\nnope I\n").unwrap();
+        let none = a.join("none");
+        let (schema, problems) = Schema::with_dirs(&[a.clone(), none.clone(), b.clone()]);
+        assert!(schema.get(&KeywordName::parse("THING")).is_some());
+        assert_eq!(problems.len(), 3, "{problems:?}");
+        assert!(problems[0].starts_with(&format!("{}: ", none.display())));
+        assert_eq!(problems[1], format!("*THING of {} overrides the one of {}", b.display(), a.display()));
+        assert!(problems[2].contains("thing.spec:2: "), "{}", problems[2]);
+    }
+}
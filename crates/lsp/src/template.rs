@@ -0,0 +1,836 @@
+//! `keywords/*.k` templates into keyword schema.
+//! `build.rs` includes this file too, so nothing from the crate in here.
+
+use std::{fmt, fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+use syntax::{
+    ast::AstNode,
+    dyna_nodes::{Header, Record},
+    keyword::{KeywordName, KwOption},
+    parse::parse_text,
+};
+
+/// fields that take the rest of the line
+pub const TEXT_FIELDS: &[&str] = &["title", "heading", "function", "expression", "path"];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeywordSchema {
+    /// as the template has it, `SECTION_SHELL_TITLE`
+    pub name: String,
+    /// a line on what it's for, from the `.spec` files
+    pub summary: Option<String>,
+    pub cards: Vec<CardSchema>,
+    /// the whole list of cards again for the next one, `*PART` with many parts
+    pub repeat: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CardSchema {
+    pub fields: Vec<FieldSchema>,
+    /// may be left out, and so may every card after it
+    pub optional: bool,
+    /// as many as there are until the next keyword, the last card only
+    pub repeat: bool,
+    /// only there if a field before says so
+    pub when: Option<Condition>,
+}
+
+/// `icomp=1`, `dattyp=0,6`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Condition {
+    pub field: String,
+    pub values: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldSchema {
+    /// lower case, as in the `$#` header
+    pub name: String,
+    /// first column, zero based
+    pub start: usize,
+    pub width: usize,
+    /// a `?` placeholder in the template, has to be filled
+    pub required: bool,
+    /// what the template writes there
+    pub default: Option<String>,
+    /// from the `.spec` files, text fields are known without one
+    pub kind: Option<Kind>,
+    pub range: Option<Range>,
+    /// what it's measured in, `length`, `stress`, in the deck's units
+    pub unit: Option<String>,
+    /// the values it takes, when it's a flag
+    pub choices: Vec<Choice>,
+    /// the choices are the usual ones, the solver takes others too
+    pub open: bool,
+    /// a negative value is a curve id instead
+    pub curve: bool,
+    /// the ID it gives to what the card defines
+    pub defines: Option<Entity>,
+    /// what its value names, the first one whose condition holds
+    pub refers: Vec<(Entity, Option<Condition>)>,
+}
+
+/// what an ID names, each has its own IDs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Entity {
+    Part,
+    Section,
+    Material,
+    Eos,
+    Hourglass,
+    /// curves, tables and functions share their IDs
+    Curve,
+    Box,
+    Coordinate,
+    Vector,
+    Node,
+    Element,
+    NodeSet,
+    PartSet,
+    SegmentSet,
+    ShellSet,
+    SolidSet,
+    BeamSet,
+    DiscreteSet,
+}
+
+/// `I`, `F` and `A` in the manual
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Kind {
+    Integer,
+    Float,
+    Text,
+}
+
+/// `(0,1]`, `[0,)` ..
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Range {
+    pub lo: Option<Bound>,
+    pub hi: Option<Bound>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Bound {
+    pub value: f64,
+    /// `[`/`]`
+    pub closed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Choice {
+    pub value: String,
+    pub meaning: String,
+}
+
+impl Entity {
+    pub const ALL: [Entity; 18] = [
+        Entity::Part,
+        Entity::Section,
+        Entity::Material,
+        Entity::Eos,
+        Entity::Hourglass,
+        Entity::Curve,
+        Entity::Box,
+        Entity::Coordinate,
+        Entity::Vector,
+        Entity::Node,
+        Entity::Element,
+        Entity::NodeSet,
+        Entity::PartSet,
+        Entity::SegmentSet,
+        Entity::ShellSet,
+        Entity::SolidSet,
+        Entity::BeamSet,
+        Entity::DiscreteSet,
+    ];
+
+    /// as `.spec` files write it
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Entity::Part => "part",
+            Entity::Section => "section",
+            Entity::Material => "mat",
+            Entity::Eos => "eos",
+            Entity::Hourglass => "hourglass",
+            Entity::Curve => "curve",
+            Entity::Box => "box",
+            Entity::Coordinate => "coord",
+            Entity::Vector => "vector",
+            Entity::Node => "node",
+            Entity::Element => "element",
+            Entity::NodeSet => "set_node",
+            Entity::PartSet => "set_part",
+            Entity::SegmentSet => "set_segment",
+            Entity::ShellSet => "set_shell",
+            Entity::SolidSet => "set_solid",
+            Entity::BeamSet => "set_beam",
+            Entity::DiscreteSet => "set_discrete",
+        }
+    }
+
+    pub fn from_word(word: &str) -> Option<Entity> {
+        Entity::ALL.into_iter().find(|e| e.as_str() == word)
+    }
+}
+
+impl fmt::Display for Entity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Entity::Material => "material",
+            Entity::Eos => "equation of state",
+            Entity::Coordinate => "coordinate system",
+            Entity::NodeSet => "node set",
+            Entity::PartSet => "part set",
+            Entity::SegmentSet => "segment set",
+            Entity::ShellSet => "shell set",
+            Entity::SolidSet => "solid set",
+            Entity::BeamSet => "beam set",
+            Entity::DiscreteSet => "discrete set",
+            e => e.as_str(),
+        })
+    }
+}
+
+impl KeywordSchema {
+    pub fn keyword_name(&self) -> KeywordName {
+        KeywordName::parse(&self.name)
+    }
+}
+
+impl FieldSchema {
+    pub fn new(name: &str, start: usize, width: usize) -> FieldSchema {
+        FieldSchema {
+            name: name.to_string(),
+            start,
+            width,
+            required: false,
+            default: None,
+            kind: TEXT_FIELDS.contains(&name).then_some(Kind::Text),
+            range: None,
+            unit: None,
+            choices: vec![],
+            open: false,
+            curve: false,
+            defines: None,
+            refers: vec![],
+        }
+    }
+
+    /// what's wrong with `text` written in this field, blank is always fine
+    pub fn check(&self, text: &str) -> Result<(), String> {
+        let text = text.trim();
+        // `&name` or `-&name` is a parameter
+        if text.is_empty() || text.trim_start_matches('-').starts_with('&') {
+            return Ok(());
+        }
+        let value = match self.kind {
+            Some(Kind::Text) | None => None,
+            Some(Kind::Integer) => match text.strip_prefix('+').unwrap_or(text).parse::<i64>() {
+                Ok(v) => Some(v as f64),
+                Err(_) => return Err(format!("`{text}` is not an integer")),
+            },
+            Some(Kind::Float) => match number(text) {
+                Some(v) => Some(v),
+                None => return Err(format!("`{text}` is not a number")),
+            },
+        };
+        if self.curve && value.is_some_and(|v| v < 0.0) {
+            return Ok(());
+        }
+        if let (Some(range), Some(v)) = (self.range, value) {
+            if !range.contains(v) {
+                return Err(format!("`{text}` is out of range {range}"));
+            }
+        }
+        if !self.choices.is_empty() && !self.open && self.choice(text).is_none() {
+            let all: Vec<_> = self.choices.iter().map(|c| c.value.as_str()).collect();
+            return Err(format!("`{text}` is not one of {}", all.join(", ")));
+        }
+        Ok(())
+    }
+
+    /// the choice `text` picks, `1.0` is `1`
+    pub fn choice(&self, text: &str) -> Option<&Choice> {
+        let text = text.trim();
+        self.choices.iter().find(|c| match (number(&c.value), number(text)) {
+            (Some(a), Some(b)) => a == b,
+            _ => c.value.eq_ignore_ascii_case(text),
+        })
+    }
+}
+
+impl Condition {
+    /// `value` is what the field has, default filled in
+    pub fn holds(&self, value: &str) -> bool {
+        self.values.iter().any(|v| match (number(v), number(value)) {
+            (Some(a), Some(b)) => a == b,
+            _ => v.eq_ignore_ascii_case(value.trim()),
+        })
+    }
+
+    fn parse(text: &str) -> Option<Condition> {
+        let (field, values) = text.split_once('=')?;
+        Some(Condition {
+            field: field.to_lowercase(),
+            values: values.split(',').map(|v| v.to_string()).collect(),
+        })
+    }
+}
+
+impl Range {
+    pub fn contains(&self, v: f64) -> bool {
+        let above = self.lo.map_or(true, |b| v > b.value || b.closed && v == b.value);
+        let below = self.hi.map_or(true, |b| v < b.value || b.closed && v == b.value);
+        above && below
+    }
+
+    fn parse(text: &str) -> Option<Range> {
+        let mut chars = text.chars();
+        let (open, close) = (chars.next()?, chars.next_back()?);
+        let (lo, hi) = chars.as_str().split_once(',')?;
+        let bound = |s: &str, closed: bool| -> Option<Option<Bound>> {
+            match s.trim() {
+                "" => Some(None),
+                s => Some(Some(Bound { value: number(s)?, closed })),
+            }
+        };
+        let lo = match open {
+            '[' => bound(lo, true)?,
+            '(' => bound(lo, false)?,
+            _ => return None,
+        };
+        let hi = match close {
+            ']' => bound(hi, true)?,
+            ')' => bound(hi, false)?,
+            _ => return None,
+        };
+        Some(Range { lo, hi })
+    }
+}
+
+impl fmt::Display for Range {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.lo {
+            Some(b) => write!(f, "{}{}", if b.closed { '[' } else { '(' }, b.value)?,
+            None => f.write_str("(")?,
+        }
+        f.write_str(",")?;
+        match self.hi {
+            Some(b) => write!(f, "{}{}", b.value, if b.closed { ']' } else { ')' }),
+            None => f.write_str(")"),
+        }
+    }
+}
+
+/// fortran reads `1.5d3` too
+pub fn number(text: &str) -> Option<f64> {
+    let v: f64 = text.trim().replace(['d', 'D'], "e").parse().ok()?;
+    v.is_finite().then_some(v)
+}
+
+impl CardSchema {
+    pub fn new(fields: Vec<FieldSchema>) -> CardSchema {
+        CardSchema {
+            fields,
+            optional: false,
+            repeat: false,
+            when: None,
+        }
+    }
+
+    /// nothing in it has to be filled, fine to leave out at the end
+    pub fn is_optional(&self) -> bool {
+        self.optional || self.repeat || self.fields.iter().all(|f| !f.required)
+    }
+
+    /// `$# title` and nothing else
+    pub fn is_title(&self) -> bool {
+        matches!(&self.fields[..], [f] if TEXT_FIELDS.contains(&f.name.as_str()))
+    }
+
+    /// `$#     cid title`, the card `_ID` adds
+    pub fn is_id(&self) -> bool {
+        matches!(&self.fields[..], [_, f] if TEXT_FIELDS.contains(&f.name.as_str()))
+    }
+}
+
+/// every keyword of a template file
+pub fn from_template(text: &str) -> Vec<KeywordSchema> {
+    let parse = parse_text(text);
+    let mut keywords = vec![];
+    for card in parse.tree().cards() {
+        let Some(kwd) = card.keyword() else {continue};
+        let mut cards = vec![];
+        let mut layout: Option<Vec<(String, usize, usize)>> = None;
+        for record in card.records() {
+            if let Some(header) = record.header() {
+                layout = Some(columns(&header));
+            }
+            let line = data_line(&record);
+            let fields = match &layout {
+                Some(cols) => cols
+                    .iter()
+                    .map(|(name, start, width)| field(name, *start, *width, &line))
+                    .collect(),
+                None => placeholders(&line),
+            };
+            cards.push(CardSchema::new(fields));
+        }
+        keywords.push(KeywordSchema {
+            name: kwd.name().to_string(),
+            summary: None,
+            cards,
+            repeat: false,
+        });
+    }
+    keywords
+}
+
+/// templates under `dir` with its `.spec` files applied
+pub fn from_dir(dir: &Path) -> std::io::Result<Vec<KeywordSchema>> {
+    let mut keywords = read_templates(dir)?;
+    read_specs(dir, &mut keywords)?;
+    Ok(keywords)
+}
+
+/// all templates under `dir`, sorted by name. files starting with `_` are whole decks
+/// and skipped. a keyword named after its file wins over the same keyword in other files
+pub fn read_templates(dir: &Path) -> std::io::Result<Vec<KeywordSchema>> {
+    let mut files = vec![];
+    walk(dir, "k", &mut files)?;
+    files.sort();
+    let mut owned: Vec<KeywordSchema> = vec![];
+    let mut borrowed: Vec<KeywordSchema> = vec![];
+    for file in files {
+        let stem = file.file_stem().unwrap_or_default().to_string_lossy().to_uppercase();
+        if stem.starts_with('_') {
+            continue;
+        }
+        let text = fs::read_to_string(&file)?;
+        for kw in from_template(&text) {
+            let name = kw.keyword_name();
+            let is_own = name.same_as(&KeywordName::parse(&stem))
+                || (name.has(KwOption::Title) && kw.name == format!("{stem}_TITLE"));
+            match is_own {
+                true => owned.push(kw),
+                false => borrowed.push(kw),
+            }
+        }
+    }
+    for kw in borrowed {
+        if !owned.iter().any(|o| o.name == kw.name) {
+            owned.push(kw);
+        }
+    }
+    owned.sort_by(|a, b| a.name.cmp(&b.name));
+    owned.dedup_by(|a, b| a.name == b.name);
+    Ok(owned)
+}
+
+/// the `.spec` files under `dir`, on whatever keywords there are by now
+pub fn read_specs(dir: &Path, keywords: &mut [KeywordSchema]) -> std::io::Result<()> {
+    let mut specs = vec![];
+    walk(dir, "spec", &mut specs)?;
+    specs.sort();
+    for file in specs {
+        let text = fs::read_to_string(&file)?;
+        apply_spec(keywords, &text).map_err(|e| {
+            std::io::Error::new(std::io::ErrorKind::InvalidData, format!("{}:{e}", file.display()))
+        })?;
+    }
+    Ok(())
+}
+
+/// a `.spec` file says what the fields of some keywords take:
+///
+/// ```text
+/// $ comment
+/// $ repeat if the cards can be given again for the next one
+/// *SECTION_SHELL  repeat
+/// about shell element formulation and thickness
+/// $ name  I/F/A  range   curve if a negative value is a curve id, unit
+/// shrf    F      (0,1]
+/// t1      F      [0,)    unit=length
+/// icomp   I
+///     0   no composite
+///     1   integration point angles follow
+/// $ open if the choices are only the usual ones
+/// elform  I  open
+///     2   Belytschko-Tsay
+/// $ numbered as in the manual, title and id cards don't count
+/// card 3  if icomp=1
+/// $ the ID the card defines, the IDs a field names. the type may be left out
+/// *CONTACT_AUTOMATIC_SINGLE_SURFACE
+/// ssid    ref=set_segment if sstyp=0  ref=set_part if sstyp=2  ref=part if sstyp=3
+/// ```
+///
+/// a card is `optional`, `repeat` or `if field=value,value`.
+/// a keyword covers its `_TITLE`/`_ID` variants
+pub fn apply_spec(keywords: &mut [KeywordSchema], text: &str) -> Result<(), String> {
+    let mut current: Option<KeywordName> = None;
+    // fields the choices below go to
+    let mut last: Vec<(usize, usize, usize)> = vec![];
+    for (n, line) in text.lines().enumerate() {
+        let err = |msg: String| format!("{}: {msg}", n + 1);
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('$') {
+            continue;
+        }
+        if let Some(line) = line.strip_prefix('*') {
+            let mut words = line.split_whitespace();
+            let name = KeywordName::parse(words.next().unwrap_or_default());
+            if !keywords.iter().any(|k| k.keyword_name().same_family(&name)) {
+                return Err(err(format!("no template for *{name}")));
+            }
+            let mut repeat = false;
+            for word in words {
+                match word {
+                    "repeat" => repeat = true,
+                    w => return Err(err(format!("what is `{w}`?"))),
+                }
+            }
+            for kw in keywords.iter_mut().filter(|k| k.keyword_name().same_family(&name)) {
+                kw.repeat = repeat;
+            }
+            current = Some(name);
+            last.clear();
+            continue;
+        }
+        let Some(kwd) = &current else {
+            return Err(err("field before any *KEYWORD".to_string()));
+        };
+        if let Some(rest) = line.strip_prefix("about ") {
+            for kw in keywords.iter_mut().filter(|k| k.keyword_name().same_family(kwd)) {
+                kw.summary = Some(rest.trim().to_string());
+            }
+            continue;
+        }
+        if let Some(rest) = line.strip_prefix("card ") {
+            last.clear();
+            let mut words = rest.split_whitespace();
+            let n: usize = match words.next().and_then(|n| n.parse().ok()) {
+                Some(n) if n > 0 => n,
+                _ => return Err(err("cards are numbered from 1".to_string())),
+            };
+            let (mut optional, mut repeat, mut when) = (false, false, None);
+            while let Some(word) = words.next() {
+                match word {
+                    "optional" => optional = true,
+                    "repeat" => repeat = true,
+                    "if" => match words.next().and_then(Condition::parse) {
+                        Some(c) => when = Some(c),
+                        None => return Err(err("`if` wants field=value".to_string())),
+                    },
+                    w => return Err(err(format!("what is `{w}`?"))),
+                }
+            }
+            for kw in keywords.iter_mut().filter(|k| k.keyword_name().same_family(kwd)) {
+                let name = kw.keyword_name();
+                let lead = name.has(KwOption::Title) as usize + name.has(KwOption::Id) as usize;
+                let i = lead + n - 1;
+                if i >= kw.cards.len() {
+                    return Err(err(format!("*{} has no card {n}", kw.name)));
+                }
+                if repeat {
+                    // templates show a couple of them, `a1 o1` twice
+                    let same = |c: &CardSchema| {
+                        c.fields.iter().map(|f| &f.name).eq(kw.cards[i].fields.iter().map(|f| &f.name))
+                    };
+                    if !kw.cards[i + 1..].iter().all(same) {
+                        return Err(err(format!("card {n} of *{} is not the last", kw.name)));
+                    }
+                    kw.cards.truncate(i + 1);
+                }
+                let card = &mut kw.cards[i];
+                card.optional = optional;
+                card.repeat = repeat;
+                card.when = when.clone();
+            }
+            continue;
+        }
+        if line.starts_with([' ', '\t']) {
+            if last.is_empty() {
+                return Err(err("choice before any field".to_string()));
+            }
+            let line = line.trim();
+            let (value, meaning) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let choice = Choice {
+                value: value.to_string(),
+                meaning: meaning.trim().to_string(),
+            };
+            for &(k, c, f) in &last {
+                keywords[k].cards[c].fields[f].choices.push(choice.clone());
+            }
+            continue;
+        }
+        let mut words = line.split_whitespace().peekable();
+        let name = words.next().unwrap_or_default().to_lowercase();
+        let kind = match words.peek().copied() {
+            Some("I") => Some(Kind::Integer),
+            Some("F") => Some(Kind::Float),
+            Some("A") => Some(Kind::Text),
+            Some(w) if w.starts_with("def=") || w.starts_with("ref=") => None,
+            other => return Err(err(format!("`{}` is not I, F or A", other.unwrap_or_default()))),
+        };
+        if kind.is_some() {
+            words.next();
+        }
+        let (mut range, mut curve, mut unit, mut open) = (None, false, None, false);
+        let (mut defines, mut refers) = (None, vec![]);
+        let entity = |e: &str| Entity::from_word(e).ok_or_else(|| err(format!("what is a `{e}`?")));
+        while let Some(word) = words.next() {
+            match word {
+                "curve" => curve = true,
+                "open" => open = true,
+                "if" => match (refers.last_mut(), words.next().and_then(Condition::parse)) {
+                    (Some((_, when)), Some(c)) => *when = Some(c),
+                    _ => return Err(err("`if` wants a ref= before and field=value".to_string())),
+                },
+                w if w.starts_with(['(', '[']) => match Range::parse(w) {
+                    Some(r) => range = Some(r),
+                    None => return Err(err(format!("bad range `{w}`"))),
+                },
+                w => match w.split_once('=') {
+                    Some(("unit", u)) => unit = Some(u.to_string()),
+                    Some(("def", e)) => defines = Some(entity(e)?),
+                    Some(("ref", e)) => refers.push((entity(e)?, None)),
+                    _ => return Err(err(format!("what is `{w}`?"))),
+                },
+            }
+        }
+        last.clear();
+        for (k, kw) in keywords.iter_mut().enumerate() {
+            if !kw.keyword_name().same_family(kwd) {
+                continue;
+            }
+            for (c, card) in kw.cards.iter_mut().enumerate() {
+                for (f, field) in card.fields.iter_mut().enumerate() {
+                    if field.name != name {
+                        continue;
+                    }
+                    // ID marks alone leave the rest as it was
+                    if let Some(kind) = kind {
+                        field.kind = Some(kind);
+                        field.range = range;
+                        field.curve = curve;
+                        field.open = open;
+                        field.unit = unit.clone();
+                        field.choices.clear();
+                    }
+                    if defines.is_some() {
+                        field.defines = defines;
+                    }
+                    if !refers.is_empty() {
+                        field.refers = refers.clone();
+                    }
+                    last.push((k, c, f));
+                }
+            }
+        }
+        if last.is_empty() {
+            return Err(err(format!("*{kwd} has no field `{name}`")));
+        }
+    }
+    Ok(())
+}
+
+fn walk(dir: &Path, ext: &str, files: &mut Vec<std::path::PathBuf>) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            walk(&path, ext, files)?;
+        } else if path.extension().is_some_and(|e| e == ext) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// the data line, without the header and comments above it
+fn data_line(record: &Record) -> String {
+    let text = record.syntax().text().to_string();
+    let text = text.trim_end_matches(['\n', '\r']);
+    match text.rfind('\n') {
+        Some(i) => text[i + 1..].to_string(),
+        None => text.to_string(),
+    }
+}
+
+/// `(name, start, width)` from a header. names are right aligned, but templates
+/// don't always manage, so ends are snapped to the grid
+fn columns(header: &Header) -> Vec<(String, usize, usize)> {
+    let snap = |end: usize| match end % 8 == 0 || end % 10 == 0 {
+        true => end,
+        false => (end + 5) / 10 * 10,
+    };
+    let cols: Vec<(String, usize)> = header
+        .columns()
+        .into_iter()
+        .map(|c| {
+            // `time [s]` is `time`
+            let name = c.name.split(" [").next().unwrap_or_default();
+            (name.to_lowercase(), snap(c.end))
+        })
+        .collect();
+    let ends: Vec<usize> = cols.iter().map(|c| c.1).collect();
+    let widths: Vec<usize> = ends
+        .iter()
+        .scan(0, |prev, &end| {
+            let w = end.saturating_sub(*prev);
+            *prev = end;
+            Some(w)
+        })
+        .collect();
+    let on_grid = ends.iter().all(|e| e % 10 == 0);
+    let uniform = widths.windows(2).all(|w| w[0] == w[1]);
+    let mut prev = 0;
+    let mut layout = vec![];
+    for (name, end) in cols {
+        let mut start = prev;
+        let mut end = end.max(start + 1);
+        if TEXT_FIELDS.contains(&name.as_str()) {
+            end = end.max(80);
+        } else if on_grid && !uniform && end - start > 10 {
+            // blank columns before it, `idroff              prefix`
+            start = end - 10;
+        }
+        prev = end;
+        layout.push((name, start, end - start));
+    }
+    layout
+}
+
+fn field(name: &str, start: usize, width: usize, line: &str) -> FieldSchema {
+    let value = line.get(start..(start + width).min(line.len())).unwrap_or_default().trim();
+    let required = value.contains('?');
+    FieldSchema {
+        required,
+        default: (!required && !value.is_empty()).then(|| value.to_string()),
+        ..FieldSchema::new(name, start, width)
+    }
+}
+
+/// no header, `?path?` is a field named `path` to the end of line
+fn placeholders(line: &str) -> Vec<FieldSchema> {
+    let mut fields = vec![];
+    let mut col = 0;
+    for word in line.split(' ') {
+        if let Some(name) = word.strip_prefix('?').and_then(|w| w.strip_suffix('?')) {
+            let width = 80usize.saturating_sub(col).max(word.len());
+            fields.push(FieldSchema {
+                required: true,
+                ..FieldSchema::new(name, col, width)
+            });
+        }
+        col += word.len() + 1;
+    }
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{apply_spec, from_template, read_templates, FieldSchema, KeywordSchema, Range};
+
+    const SHELL: &str = "*SECTION_SHELL\n$#   secid    elform      shrf\n         ?         2     0.833\n";
+
+    fn field<'a>(keywords: &'a [KeywordSchema], name: &str) -> &'a FieldSchema {
+        keywords[0].cards[0].fields.iter().find(|f| f.name == name).unwrap()
+    }
+
+    #[test]
+    fn ranges() {
+        let range = |text: &str| Range::parse(text).map(|r| r.to_string());
+        assert_eq!(range("[0,1)"), Some("[0,1)".to_string()));
+        assert_eq!(range("(,0]"), Some("(,0]".to_string()));
+        assert!(Range::parse("(0,)").unwrap().contains(1e9));
+        assert!(!Range::parse("(0,)").unwrap().contains(0.));
+        // not a range, and no panic on what isn't ascii
+        for bad in ["", "(", "[0,1", "0,1", "[a,1]", "é", "[0,1é", "é0,1]", "[0,1]é"] {
+            assert_eq!(range(bad), None, "{bad}");
+        }
+    }
+
+    #[test]
+    fn types_ranges_and_choices() {
+        let mut keywords = from_template(SHELL);
+        let spec = "*SECTION_SHELL\nsecid I (0,)\nelform I\n    2 Belytschko-Tsay\n    16 fully integrated\nshrf F (0,1] unit=none\n";
+        apply_spec(&mut keywords, spec).unwrap();
+        let secid = field(&keywords, "secid");
+        assert!(secid.check("1").is_ok());
+        assert!(secid.check("1.5").is_err());
+        assert!(secid.check("0").is_err());
+        assert!(secid.check("&id").is_ok());
+        assert!(secid.check("").is_ok());
+        let elform = field(&keywords, "elform");
+        assert!(elform.check("16").is_ok());
+        assert_eq!(elform.check("25"), Err("`25` is not one of 2, 16".to_string()));
+        assert_eq!(elform.choice("2.0").map(|c| c.meaning.as_str()), Some("Belytschko-Tsay"));
+        let shrf = field(&keywords, "shrf");
+        assert!(shrf.check("0.833").is_ok());
+        assert!(shrf.check("1.5d0").is_err());
+        assert_eq!(shrf.unit.as_deref(), Some("none"));
+    }
+
+    #[test]
+    fn open_choices_take_others() {
+        let mut keywords = from_template(SHELL);
+        apply_spec(&mut keywords, "*SECTION_SHELL\nelform I open\n    2 Belytschko-Tsay\n").unwrap();
+        let elform = field(&keywords, "elform");
+        assert!(elform.open);
+        assert!(elform.check("25").is_ok());
+        assert!(elform.check("x").is_err());
+        assert!(elform.choice("25").is_none());
+    }
+
+    #[test]
+    fn bad_specs() {
+        let mut keywords = from_template(SHELL);
+        for bad in ["secid I", "*SECTION_SHELL\nnope I", "*SECTION_SHELL\nsecid X", "*SECTION_SHELL\nshrf F [0,1é"] {
+            assert!(apply_spec(&mut keywords, bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn fields_from_headers() {
+        let keywords = from_template(SHELL);
+        assert_eq!(keywords.len(), 1);
+        assert_eq!(keywords[0].name, "SECTION_SHELL");
+        let fields: Vec<_> = keywords[0].cards[0].fields.iter().map(|f| (f.name.as_str(), f.start, f.width)).collect();
+        assert_eq!(fields, vec![("secid", 0, 10), ("elform", 10, 10), ("shrf", 20, 10)]);
+        let secid = field(&keywords, "secid");
+        assert!(secid.required && secid.default.is_none());
+        assert_eq!(field(&keywords, "shrf").default.as_deref(), Some("0.833"));
+    }
+
+    #[test]
+    fn titles_and_placeholders() {
+        let text = "*PART_TITLE\n$# title\n?title?\n$#     pid     secid       mid\n         ?         ?         ?\n*INCLUDE\n?path?\n";
+        let keywords = from_template(text);
+        let names: Vec<_> = keywords.iter().map(|k| k.name.as_str()).collect();
+        assert_eq!(names, vec!["PART_TITLE", "INCLUDE"]);
+        let part = &keywords[0];
+        assert!(part.cards[0].is_title());
+        assert_eq!((part.cards[0].fields[0].start, part.cards[0].fields[0].width), (0, 80));
+        assert_eq!(part.cards[1].fields.len(), 3);
+        // no header, the `?path?` is the field
+        let path = &keywords[1].cards[0].fields[0];
+        assert_eq!((path.name.as_str(), path.width, path.required), ("path", 80, true));
+    }
+
+    #[test]
+    fn own_file_wins() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        fs::write(dir.join("section_shell.k"), SHELL).unwrap();
+        let other = "*SECTION_SHELL\n$#   secid\n         ?\n*SECTION_BEAM\n$#   secid\n         ?\n";
+        fs::write(dir.join("other.k"), other).unwrap();
+        fs::write(dir.join("_deck.k"), "*NODE\n").unwrap();
+        let keywords = read_templates(dir).unwrap();
+        let names: Vec<_> = keywords.iter().map(|k| k.name.as_str()).collect();
+        assert_eq!(names, vec!["SECTION_BEAM", "SECTION_SHELL"]);
+        assert_eq!(keywords[1].cards[0].fields.len(), 3);
+    }
+}
@@ -0,0 +1,344 @@
+//! what `*INCLUDE_TRANSFORM` does to the entities of the file it includes:
+//! ID offsets, unit factors and a `*DEFINE_TRANSFORMATION` on coordinates
+
+use std::collections::HashMap;
+
+use crate::template::number;
+
+/// which offset of `*INCLUDE_TRANSFORM` an ID gets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IdKind {
+    /// IDNOFF
+    Node,
+    /// IDEOFF
+    Element,
+    /// IDPOFF
+    Part,
+    /// IDMOFF
+    Material,
+    /// IDSOFF
+    Set,
+    /// IDFOFF, curves, tables and functions
+    Curve,
+    /// IDDOFF, any other `*DEFINE`
+    Define,
+    /// IDEOOFF
+    Eos,
+    /// IDHOFF
+    Hourglass,
+    /// IDROFF, all the rest
+    Other,
+}
+
+impl IdKind {
+    pub const ALL: [IdKind; 10] = [
+        IdKind::Node,
+        IdKind::Element,
+        IdKind::Part,
+        IdKind::Material,
+        IdKind::Set,
+        IdKind::Curve,
+        IdKind::Define,
+        IdKind::Eos,
+        IdKind::Hourglass,
+        IdKind::Other,
+    ];
+}
+
+/// `x' = m * (x, 1)`, rows of a 3x4
+pub type Affine = [[f64; 4]; 3];
+
+pub const IDENTITY: Affine = [[1., 0., 0., 0.], [0., 1., 0., 0.], [0., 0., 1., 0.]];
+
+/// everything a chain of `*INCLUDE_TRANSFORM`s does, outermost last
+#[derive(Debug, Clone)]
+pub struct Transform {
+    /// by `IdKind`
+    pub offsets: [i64; 10],
+    /// FCTMAS, FCTTIM, FCTLEN, FCTTEM
+    pub mass: f64,
+    pub time: f64,
+    pub length: f64,
+    pub temperature: f64,
+    /// FCTLEN and then TRANID on coordinates
+    pub matrix: Affine,
+}
+
+// bit for bit, factors multiplied up may still end in a NaN
+impl PartialEq for Transform {
+    fn eq(&self, other: &Transform) -> bool {
+        let bits = |t: &Transform| {
+            let factors = [t.mass, t.time, t.length, t.temperature].map(f64::to_bits);
+            (factors, t.matrix.map(|r| r.map(f64::to_bits)))
+        };
+        self.offsets == other.offsets && bits(self) == bits(other)
+    }
+}
+
+impl Eq for Transform {}
+
+impl Default for Transform {
+    fn default() -> Transform {
+        Transform {
+            offsets: [0; 10],
+            mass: 1.,
+            time: 1.,
+            length: 1.,
+            temperature: 1.,
+            matrix: IDENTITY,
+        }
+    }
+}
+
+impl Transform {
+    pub fn offset(&self, kind: IdKind) -> i64 {
+        self.offsets[kind as usize]
+    }
+
+    /// the ID the solver sees. 0 means none and stays, a negative ID keeps its sign
+    pub fn id(&self, kind: IdKind, id: i64) -> i64 {
+        match id {
+            0 => 0,
+            id if id < 0 => id - self.offset(kind),
+            id => id + self.offset(kind),
+        }
+    }
+
+    pub fn point(&self, p: [f64; 3]) -> [f64; 3] {
+        apply(&self.matrix, p)
+    }
+
+    /// `inner` inside a file already transformed by `self`
+    pub fn then(&self, inner: &Transform) -> Transform {
+        let mut offsets = self.offsets;
+        for (o, i) in offsets.iter_mut().zip(inner.offsets) {
+            *o += i;
+        }
+        Transform {
+            offsets,
+            mass: self.mass * inner.mass,
+            time: self.time * inner.time,
+            length: self.length * inner.length,
+            temperature: self.temperature * inner.temperature,
+            matrix: compose(&self.matrix, &inner.matrix),
+        }
+    }
+}
+
+/// the fields of `*INCLUDE_TRANSFORM` after the file name, blank as they come
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IncludeTransform {
+    /// by `IdKind`
+    pub offsets: [i64; 10],
+    /// FCTMAS, FCTTIM, FCTLEN, FCTTEM, 0 is 1
+    pub factors: [f64; 4],
+    pub tranid: i64,
+}
+
+impl IncludeTransform {
+    /// records of the card past the file name, each as its field values
+    pub fn read(records: &[Vec<Option<String>>]) -> Result<IncludeTransform, String> {
+        let value = |r: usize, f: usize| records.get(r).and_then(|r| r.get(f)).cloned().flatten();
+        let int = |r: usize, f: usize| match value(r, f) {
+            None => Ok(0),
+            Some(v) => v.trim_start_matches('+').parse::<i64>().map_err(|_| format!("`{v}` is not an integer")),
+        };
+        let float = |r: usize, f: usize| match value(r, f) {
+            None => Ok(1.),
+            Some(v) => match number(&v) {
+                Some(0.) => Ok(1.),
+                Some(x) => Ok(x),
+                None => Err(format!("`{v}` is not a number")),
+            },
+        };
+        let mut offsets = [0; 10];
+        // idnoff ideoff idpoff idmoff idsoff idfoff iddoff
+        for (i, o) in offsets.iter_mut().enumerate().take(7) {
+            *o = int(0, i)?;
+        }
+        // then idroff ideooff idhoff
+        offsets[IdKind::Other as usize] = int(1, 0)?;
+        offsets[IdKind::Eos as usize] = int(1, 1)?;
+        offsets[IdKind::Hourglass as usize] = int(1, 2)?;
+        let mut factors = [1.; 4];
+        for (i, f) in factors.iter_mut().enumerate() {
+            *f = float(2, i)?;
+        }
+        Ok(IncludeTransform {
+            offsets,
+            factors,
+            tranid: int(3, 0)?,
+        })
+    }
+
+    /// with the `*DEFINE_TRANSFORMATION` TRANID stands for
+    pub fn transform(&self, tranid: Option<&Affine>) -> Transform {
+        let [mass, time, length, temperature] = self.factors;
+        let scale = [
+            [length, 0., 0., 0.],
+            [0., length, 0., 0.],
+            [0., 0., length, 0.],
+        ];
+        Transform {
+            offsets: self.offsets,
+            mass,
+            time,
+            length,
+            temperature,
+            matrix: compose(tranid.unwrap_or(&IDENTITY), &scale),
+        }
+    }
+}
+
+/// `*DEFINE_TRANSFORMATION` lines, `option a1 .. a7`, one after the other.
+/// TRANSL, SCALE, ROTATE and POINT are known
+pub fn define_transformation(lines: &[(String, [f64; 7])]) -> Result<Affine, String> {
+    let mut m = IDENTITY;
+    let mut points: HashMap<i64, [f64; 3]> = HashMap::new();
+    for (option, a) in lines {
+        let step = match option.to_ascii_uppercase().as_str() {
+            "TRANSL" => [
+                [1., 0., 0., a[0]],
+                [0., 1., 0., a[1]],
+                [0., 0., 1., a[2]],
+            ],
+            "SCALE" => [
+                [a[0], 0., 0., 0.],
+                [0., a[1], 0., 0.],
+                [0., 0., a[2], 0.],
+            ],
+            "POINT" => {
+                points.insert(a[0] as i64, [a[1], a[2], a[3]]);
+                continue;
+            }
+            // about the axis through two POINTs when a4 to a7 are all 0
+            "ROTATE" if a[3..].iter().all(|v| *v == 0.) => {
+                let point = |id: f64| {
+                    points
+                        .get(&(id as i64))
+                        .copied()
+                        .ok_or_else(|| format!("no POINT {id}"))
+                };
+                let (p, q) = (point(a[0])?, point(a[1])?);
+                rotation([q[0] - p[0], q[1] - p[1], q[2] - p[2]], p, a[2])
+            }
+            "ROTATE" => rotation([a[0], a[1], a[2]], [a[3], a[4], a[5]], a[6]),
+            other => return Err(format!("`{other}` transformations are not supported")),
+        };
+        m = compose(&step, &m);
+    }
+    Ok(m)
+}
+
+/// by `degrees` about the axis along `dir` through `at`
+fn rotation(dir: [f64; 3], at: [f64; 3], degrees: f64) -> Affine {
+    let len = (dir[0] * dir[0] + dir[1] * dir[1] + dir[2] * dir[2]).sqrt();
+    if len == 0. {
+        return IDENTITY;
+    }
+    let [x, y, z] = dir.map(|d| d / len);
+    let (s, c) = degrees.to_radians().sin_cos();
+    let t = 1. - c;
+    let r = [
+        [t * x * x + c, t * x * y - s * z, t * x * z + s * y],
+        [t * x * y + s * z, t * y * y + c, t * y * z - s * x],
+        [t * x * z - s * y, t * y * z + s * x, t * z * z + c],
+    ];
+    // x' = r (x - at) + at
+    let mut m = IDENTITY;
+    for i in 0..3 {
+        m[i][..3].copy_from_slice(&r[i]);
+        m[i][3] = at[i] - (r[i][0] * at[0] + r[i][1] * at[1] + r[i][2] * at[2]);
+    }
+    m
+}
+
+/// `outer` after `inner`
+fn compose(outer: &Affine, inner: &Affine) -> Affine {
+    let mut m = [[0.; 4]; 3];
+    for i in 0..3 {
+        for j in 0..4 {
+            m[i][j] = (0..3).map(|k| outer[i][k] * inner[k][j]).sum::<f64>();
+        }
+        m[i][3] += outer[i][3];
+    }
+    m
+}
+
+fn apply(m: &Affine, p: [f64; 3]) -> [f64; 3] {
+    let row = |r: &[f64; 4]| r[0] * p[0] + r[1] * p[1] + r[2] * p[2] + r[3];
+    [row(&m[0]), row(&m[1]), row(&m[2])]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{define_transformation, IdKind, IncludeTransform, Transform};
+
+    fn records(lines: &[&[&str]]) -> Vec<Vec<Option<String>>> {
+        let value = |v: &&str| (!v.is_empty()).then(|| v.to_string());
+        lines.iter().map(|l| l.iter().map(value).collect()).collect()
+    }
+
+    fn close(a: [f64; 3], b: [f64; 3]) -> bool {
+        a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-9)
+    }
+
+    #[test]
+    fn offsets_by_kind() {
+        let values = records(&[&["1000", "2000", "3000", "4000", "5000", "6000", "7000"], &["8000", "9000", "10000"]]);
+        let t = IncludeTransform::read(&values).unwrap().transform(None);
+        let by_kind = IdKind::ALL.map(|k| t.offset(k));
+        assert_eq!(by_kind, [1000, 2000, 3000, 4000, 5000, 6000, 7000, 9000, 10000, 8000]);
+        assert_eq!(t.id(IdKind::Eos, 1), 9001);
+        assert_eq!(t.id(IdKind::Hourglass, 2), 10002);
+        // a negative ID is a curve, 0 is none
+        assert_eq!(t.id(IdKind::Curve, -5), -6005);
+        assert_eq!(t.id(IdKind::Part, 0), 0);
+    }
+
+    #[test]
+    fn blanks_and_zero_factors() {
+        let values = records(&[&["", "10"], &[], &["0", "", "25.4"], &["3"]]);
+        let t = IncludeTransform::read(&values).unwrap();
+        assert_eq!(t.offsets[IdKind::Element as usize], 10);
+        assert_eq!(t.offsets[IdKind::Node as usize], 0);
+        assert_eq!(t.factors, [1., 1., 25.4, 1.]);
+        assert_eq!(t.tranid, 3);
+        let bad = records(&[&["1.5"]]);
+        assert_eq!(IncludeTransform::read(&bad), Err("`1.5` is not an integer".to_string()));
+        let bad = records(&[&[], &[], &["x"]]);
+        assert_eq!(IncludeTransform::read(&bad), Err("`x` is not a number".to_string()));
+    }
+
+    #[test]
+    fn nested_includes_add_up() {
+        let outer = IncludeTransform::read(&records(&[&["100"], &[], &["", "", "2"]])).unwrap().transform(None);
+        let inner = IncludeTransform::read(&records(&[&["10"], &[], &["", "", "3"]])).unwrap().transform(None);
+        let both = outer.then(&inner);
+        assert_eq!(both.id(IdKind::Node, 1), 111);
+        assert_eq!(both.length, 6.);
+        assert!(close(both.point([1., 2., 3.]), [6., 12., 18.]));
+        assert_eq!(Transform::default().then(&inner), inner);
+        let nan = Transform { length: f64::INFINITY * 0., ..inner.clone() };
+        assert!(nan == nan.clone() && nan != inner);
+    }
+
+    #[test]
+    fn define_transformations() {
+        let line = |option: &str, a: &[f64]| {
+            let mut all = [0.; 7];
+            all[..a.len()].copy_from_slice(a);
+            (option.to_string(), all)
+        };
+        let m = define_transformation(&[line("scale", &[2., 2., 2.]), line("TRANSL", &[1., 0., 0.])]).unwrap();
+        let t = IncludeTransform::read(&[]).unwrap().transform(Some(&m));
+        assert!(close(t.point([1., 1., 1.]), [3., 2., 2.]));
+        // 90 degrees about z through (1, 0, 0), by two points
+        let lines = [line("POINT", &[1., 1., 0., 0.]), line("POINT", &[2., 1., 0., 1.]), line("ROTATE", &[1., 2., 90.])];
+        let m = define_transformation(&lines).unwrap();
+        let t = IncludeTransform::read(&[]).unwrap().transform(Some(&m));
+        assert!(close(t.point([2., 0., 0.]), [1., 1., 0.]));
+        assert_eq!(define_transformation(&[line("ROTATE", &[1., 3., 90.])]), Err("no POINT 1".to_string()));
+        assert!(define_transformation(&[line("MIRROR", &[])]).is_err());
+    }
+}
@@ -52,7 +52,7 @@ pub struct AstChildren<N> {
 }
 
 impl<N> AstChildren<N> {
-    fn new(parent: &SyntaxNode) -> Self {
+    pub(crate) fn new(parent: &SyntaxNode) -> Self {
         AstChildren {
             inner: parent.children(),
             ph: PhantomData,
@@ -1,9 +1,4 @@
-use syntax::{
-    ast::AstNode,
-    dyna_nodes::KeyWord,
-    parse::parse_text,
-    syntax_node::{print, SyntaxNode},
-};
+use syntax::{ast::AstNode, parse::parse_text, syntax_node::SyntaxNode};
 
 fn main() {
     let source_code = "*MAT_ENHANCED_COMPOSITE_DAMAGE_TITLE
@@ -28,6 +23,7 @@ Default MAT54-MAT55 MAT_ENHANCED_COMPOSITE_DAMAGE
     }
 }
 
+#[allow(dead_code)]
 fn print_cst_from_env() {
     use syntax::helpers::*;
     let file = env_read();
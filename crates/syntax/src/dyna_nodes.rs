@@ -1,13 +1,30 @@
 use crate::{
-    ast::AstNode,
-    syntax_node::{SyntaxKind, SyntaxNode},
+    ast::{AstChildren, AstNode},
+    keyword::KeywordName,
+    parse::Format,
+    syntax_node::{SyntaxKind, SyntaxNode, SyntaxToken},
 };
+use rowan::TextRange;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct SourceFile {
     pub(crate) syntax: SyntaxNode,
 }
 
+impl SourceFile {
+    pub fn cards(&self) -> AstChildren<Card> {
+        AstChildren::new(&self.syntax)
+    }
+
+    /// what `*KEYWORD` sets for every card
+    pub fn format(&self) -> Format {
+        self.cards()
+            .filter_map(|c| c.keyword())
+            .find(|k| k.name().base() == "KEYWORD")
+            .map_or(Format::default(), |k| Format::default().with_options(&k.args()))
+    }
+}
+
 // should be generated code !
 impl AstNode for SourceFile {
     fn can_cast(kind: SyntaxKind) -> bool {
@@ -31,6 +48,37 @@ pub struct KeyWord {
 }
 
 impl KeyWord {
+    /// `*PART_INERTIA_TITLE+` gives `PART` with `INERTIA` and `TITLE`
+    pub fn name(&self) -> KeywordName {
+        let name: String = self
+            .syntax
+            .children_with_tokens()
+            .filter_map(|c| c.into_token())
+            .skip_while(|t| t.kind() == SyntaxKind::ASTERISK)
+            .take_while(|t| matches!(t.kind(), SyntaxKind::WORD | SyntaxKind::UNDERSCODE | SyntaxKind::NUMBER))
+            .map(|t| t.text().to_string())
+            .collect();
+        KeywordName::parse(&name)
+    }
+
+    /// format asked by the `+`, `-` or `%` suffix, `None` goes with `*KEYWORD`
+    pub fn format(&self) -> Option<Format> {
+        self.syntax
+            .children_with_tokens()
+            .filter_map(|c| c.into_token())
+            .find(|t| matches!(t.kind(), SyntaxKind::PLUS | SyntaxKind::MINUS | SyntaxKind::PERCENT))
+            .and_then(|t| Format::from_suffix(t.text()))
+    }
+
+    /// whatever follows the name, `LONG=Y` of `*KEYWORD LONG=Y`
+    pub fn args(&self) -> String {
+        let text = self.syntax.text().to_string();
+        let text = text.trim();
+        text.split_once(char::is_whitespace)
+            .map_or("", |(_, args)| args.trim())
+            .to_string()
+    }
+
     pub fn play(&mut self) {
         let words = self
             .syntax
@@ -57,3 +105,230 @@ impl AstNode for KeyWord {
         &self.syntax
     }
 }
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Card {
+    pub(crate) syntax: SyntaxNode,
+}
+
+impl Card {
+    pub fn keyword(&self) -> Option<KeyWord> {
+        self.syntax.children().find_map(KeyWord::cast)
+    }
+
+    /// records in order, the first one and then the rest
+    pub fn records(&self) -> impl Iterator<Item = Record> {
+        self.syntax.descendants().filter_map(Record::cast)
+    }
+
+    /// the suffix if any, else what `*KEYWORD` says
+    pub fn format(&self, global: Format) -> Format {
+        self.keyword().and_then(|k| k.format()).unwrap_or(global)
+    }
+}
+
+impl AstNode for Card {
+    fn can_cast(kind: SyntaxKind) -> bool {
+        kind == SyntaxKind::CARD
+    }
+    fn cast(syntax: SyntaxNode) -> Option<Self> {
+        if Self::can_cast(syntax.kind()) {
+            Some(Self { syntax })
+        } else {
+            None
+        }
+    }
+    fn syntax(&self) -> &SyntaxNode {
+        &self.syntax
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Record {
+    pub(crate) syntax: SyntaxNode,
+}
+
+impl Record {
+    pub fn fields(&self) -> AstChildren<Field> {
+        AstChildren::new(&self.syntax)
+    }
+
+    /// zero based, `None` if the line is too short to reach it
+    pub fn field(&self, n: usize) -> Option<Field> {
+        self.fields().nth(n)
+    }
+
+    /// the data line without header, comments and line break
+    pub fn line_range(&self) -> TextRange {
+        let mut start = self.syntax.text_range().start();
+        let mut end = self.syntax.text_range().end();
+        for c in self.syntax.children_with_tokens() {
+            match c.kind() {
+                SyntaxKind::HEADER | SyntaxKind::COMMENT => start = c.text_range().end(),
+                SyntaxKind::NEWLINE => end = c.text_range().start(),
+                _ => {}
+            }
+        }
+        TextRange::new(start, end.max(start))
+    }
+
+    /// comma separated instead of fixed columns
+    pub fn is_free_format(&self) -> bool {
+        self.syntax
+            .children_with_tokens()
+            .any(|c| c.kind() == SyntaxKind::COMMA)
+    }
+
+    /// the `$#` line right above, the last one if several
+    pub fn header(&self) -> Option<Header> {
+        self.syntax.children().filter_map(Header::cast).last()
+    }
+
+    /// header columns whose right edge falls inside a field, not on its boundary.
+    /// columns past the end of the line are not checked
+    pub fn misaligned_columns(&self) -> Vec<Column> {
+        let (Some(header), Some(first)) = (self.header(), self.fields().next()) else {
+            return vec![];
+        };
+        if self.is_free_format() {
+            return vec![];
+        }
+        let start = first.syntax.text_range().start();
+        let ends: Vec<usize> = self
+            .fields()
+            .map(|f| (f.syntax.text_range().end() - start).into())
+            .collect();
+        let last = ends.last().copied().unwrap_or_default();
+        header
+            .columns()
+            .into_iter()
+            .filter(|c| c.end <= last && !ends.contains(&c.end))
+            .collect()
+    }
+}
+
+impl AstNode for Record {
+    fn can_cast(kind: SyntaxKind) -> bool {
+        kind == SyntaxKind::RECORD
+    }
+    fn cast(syntax: SyntaxNode) -> Option<Self> {
+        if Self::can_cast(syntax.kind()) {
+            Some(Self { syntax })
+        } else {
+            None
+        }
+    }
+    fn syntax(&self) -> &SyntaxNode {
+        &self.syntax
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Field {
+    pub(crate) syntax: SyntaxNode,
+}
+
+impl Field {
+    /// text without padding, `None` for a blank column
+    pub fn value(&self) -> Option<String> {
+        let text = self.syntax.text().to_string();
+        let value = text.trim();
+        (!value.is_empty()).then(|| value.to_string())
+    }
+
+    pub fn is_blank(&self) -> bool {
+        self.value().is_none()
+    }
+
+    /// the `&name` in it, if the value is a parameter
+    pub fn param(&self) -> Option<SyntaxToken> {
+        self.syntax
+            .children_with_tokens()
+            .filter_map(|c| c.into_token())
+            .find(|t| t.kind() == SyntaxKind::PARAM)
+    }
+}
+
+impl AstNode for Field {
+    fn can_cast(kind: SyntaxKind) -> bool {
+        kind == SyntaxKind::FIELD
+    }
+    fn cast(syntax: SyntaxNode) -> Option<Self> {
+        if Self::can_cast(syntax.kind()) {
+            Some(Self { syntax })
+        } else {
+            None
+        }
+    }
+    fn syntax(&self) -> &SyntaxNode {
+        &self.syntax
+    }
+}
+
+/// a name of `$#` header, with the columns of the line it covers,
+/// from the end of the name before to its own end
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Column {
+    pub name: String,
+    pub range: TextRange,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Header {
+    pub(crate) syntax: SyntaxNode,
+}
+
+impl Header {
+    /// names are right aligned to their field. `time [s]` and `lco or a1`
+    /// are one name each
+    pub fn columns(&self) -> Vec<Column> {
+        let line = self.syntax.text_range().start();
+        let mut columns: Vec<Column> = vec![];
+        let mut joining = false;
+        let names = self
+            .syntax
+            .children_with_tokens()
+            .filter_map(|c| c.into_token())
+            .filter(|t| t.kind() == SyntaxKind::COLUMN);
+        for t in names {
+            let (text, range) = (t.text(), t.text_range());
+            let end = (range.end() - line).into();
+            match columns.last_mut() {
+                Some(last) if joining || text.starts_with('[') || text.eq_ignore_ascii_case("or") => {
+                    joining = text.eq_ignore_ascii_case("or");
+                    last.name = format!("{} {}", last.name, text);
+                    last.range = last.range.cover(range);
+                    last.end = end;
+                }
+                _ => {
+                    let start = columns.last().map_or(0, |c| c.end);
+                    columns.push(Column {
+                        name: text.to_string(),
+                        range,
+                        start,
+                        end,
+                    });
+                }
+            }
+        }
+        columns
+    }
+}
+
+impl AstNode for Header {
+    fn can_cast(kind: SyntaxKind) -> bool {
+        kind == SyntaxKind::HEADER
+    }
+    fn cast(syntax: SyntaxNode) -> Option<Self> {
+        if Self::can_cast(syntax.kind()) {
+            Some(Self { syntax })
+        } else {
+            None
+        }
+    }
+    fn syntax(&self) -> &SyntaxNode {
+        &self.syntax
+    }
+}
@@ -0,0 +1,181 @@
+use std::fmt;
+
+/// options a keyword may end with, each adds or changes cards of the base keyword
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum KwOption {
+    /// a title card before everything else
+    Title,
+    /// an id/heading card before everything else
+    Id,
+    Set,
+    Generate,
+    Increment,
+    Local,
+    Mpp,
+    Inertia,
+    Contact,
+    Reposition,
+    Print,
+}
+
+impl KwOption {
+    pub const ALL: [KwOption; 11] = [
+        KwOption::Title,
+        KwOption::Id,
+        KwOption::Set,
+        KwOption::Generate,
+        KwOption::Increment,
+        KwOption::Local,
+        KwOption::Mpp,
+        KwOption::Inertia,
+        KwOption::Contact,
+        KwOption::Reposition,
+        KwOption::Print,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            KwOption::Title => "TITLE",
+            KwOption::Id => "ID",
+            KwOption::Set => "SET",
+            KwOption::Generate => "GENERATE",
+            KwOption::Increment => "INCREMENT",
+            KwOption::Local => "LOCAL",
+            KwOption::Mpp => "MPP",
+            KwOption::Inertia => "INERTIA",
+            KwOption::Contact => "CONTACT",
+            KwOption::Reposition => "REPOSITION",
+            KwOption::Print => "PRINT",
+        }
+    }
+
+    pub fn from_word(word: &str) -> Option<KwOption> {
+        KwOption::ALL
+            .into_iter()
+            .find(|o| o.as_str().eq_ignore_ascii_case(word))
+    }
+}
+
+/// `PART_INERTIA_TITLE` is the `PART` family with `INERTIA` and `TITLE`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KeywordName {
+    base: String,
+    /// in the order written
+    options: Vec<KwOption>,
+}
+
+impl KeywordName {
+    /// name without `*`, options are taken off the tail as long as they are recognized,
+    /// the first word always stays in base
+    pub fn parse(name: &str) -> KeywordName {
+        let name = name.trim().trim_start_matches('*').to_ascii_uppercase();
+        let mut words: Vec<&str> = name.split('_').collect();
+        let mut options = vec![];
+        while words.len() > 1 {
+            let Some(opt) = KwOption::from_word(words[words.len() - 1]) else {break};
+            options.push(opt);
+            words.pop();
+        }
+        options.reverse();
+        KeywordName {
+            base: words.join("_"),
+            options,
+        }
+    }
+
+    pub fn base(&self) -> &str {
+        &self.base
+    }
+
+    pub fn options(&self) -> &[KwOption] {
+        &self.options
+    }
+
+    pub fn has(&self, option: KwOption) -> bool {
+        self.options.contains(&option)
+    }
+
+    /// same base and options, order of options doesn't matter
+    pub fn same_as(&self, other: &KeywordName) -> bool {
+        let sorted = |n: &KeywordName| {
+            let mut o = n.options.clone();
+            o.sort();
+            o
+        };
+        self.base == other.base && sorted(self) == sorted(other)
+    }
+
+    /// options besides `_TITLE` and `_ID`, sorted. those two only add a card in front
+    pub fn variant(&self) -> Vec<KwOption> {
+        let mut o: Vec<_> = self
+            .options
+            .iter()
+            .copied()
+            .filter(|o| !matches!(o, KwOption::Title | KwOption::Id))
+            .collect();
+        o.sort();
+        o
+    }
+
+    /// same keyword, give or take `_TITLE` and `_ID`
+    pub fn same_family(&self, other: &KeywordName) -> bool {
+        self.base == other.base && self.variant() == other.variant()
+    }
+}
+
+impl fmt::Display for KeywordName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.base)?;
+        for o in &self.options {
+            write!(f, "_{}", o.as_str())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{KeywordName, KwOption};
+    use crate::{ast::AstNode, parse::parse_text};
+
+    #[test]
+    fn options_come_off_the_tail() {
+        let name = KeywordName::parse("*PART_INERTIA_TITLE");
+        assert_eq!(name.base(), "PART");
+        assert_eq!(name.options(), &[KwOption::Inertia, KwOption::Title]);
+        assert!(name.has(KwOption::Title) && !name.has(KwOption::Id));
+        // words before one that isn't an option stay in base
+        let name = KeywordName::parse("mat_enhanced_composite_damage_title");
+        assert_eq!(name.base(), "MAT_ENHANCED_COMPOSITE_DAMAGE");
+        assert_eq!(name.to_string(), "MAT_ENHANCED_COMPOSITE_DAMAGE_TITLE");
+        let name = KeywordName::parse("SET_NODE_LIST_GENERATE");
+        assert_eq!(name.base(), "SET_NODE_LIST");
+        // the first word is never an option
+        assert_eq!(KeywordName::parse("*SET").base(), "SET");
+        assert!(KeywordName::parse("*TITLE").options().is_empty());
+    }
+
+    #[test]
+    fn same_and_family() {
+        let a = KeywordName::parse("PART_INERTIA_TITLE");
+        let b = KeywordName::parse("PART_TITLE_INERTIA");
+        assert!(a.same_as(&b));
+        assert!(!a.same_as(&KeywordName::parse("PART_INERTIA")));
+        assert!(a.same_family(&KeywordName::parse("PART_INERTIA")));
+        assert!(!a.same_family(&KeywordName::parse("PART")));
+        assert!(KeywordName::parse("PART_TITLE").same_family(&KeywordName::parse("PART")));
+        assert_eq!(a.variant(), vec![KwOption::Inertia]);
+    }
+
+    #[test]
+    fn names_of_the_keyword_line() {
+        let text = "*SECTION_SHELL_TITLE\nthin\n*MAT_ELASTIC_ID+\n*KEYWORD LONG=Y\n";
+        let file = parse_text(text).tree();
+        let keywords: Vec<_> = file.cards().filter_map(|c| c.keyword()).collect();
+        let names: Vec<_> = keywords.iter().map(|k| k.name().to_string()).collect();
+        assert_eq!(names, vec!["SECTION_SHELL_TITLE", "MAT_ELASTIC_ID", "KEYWORD"]);
+        assert_eq!(keywords[2].args(), "LONG=Y");
+        assert!(keywords[1].format().is_some());
+        assert!(keywords[0].syntax().text().to_string().starts_with("*SECTION_SHELL_TITLE"));
+    }
+}
@@ -1,5 +1,6 @@
 pub mod ast;
 pub mod dyna_nodes;
+pub mod keyword;
 pub mod parse;
 pub mod reparsing;
 pub mod syntax_error;
@@ -7,12 +8,18 @@ pub mod syntax_node;
 
 pub mod helpers {
     pub fn env_read() -> String {
-        let p = std::env::args().skip(1).next().unwrap();
+        let p = std::env::args().nth(1).unwrap();
         std::fs::read_to_string(p).unwrap()
     }
 
     pub struct Now(std::time::Instant);
 
+    impl Default for Now {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
     impl Now {
         pub fn new() -> Self {
             use std::time::Instant;
@@ -2,6 +2,7 @@ use std::{marker::PhantomData, sync::Arc};
 
 use logos::{Logos, Span};
 pub use rowan::{GreenNode, TextRange, TextSize};
+use text_edit::Indel;
 
 use syntax_node::SyntaxKind;
 
@@ -10,6 +11,8 @@ use SyntaxKind::*;
 use crate::{
     ast::AstNode,
     dyna_nodes::SourceFile,
+    keyword::{KeywordName, KwOption},
+    reparsing::incremental_reparse,
     syntax_error::SyntaxError,
     syntax_node::{self, SyntaxNode, SyntaxTreeBuilder},
 };
@@ -71,6 +74,21 @@ impl<T: AstNode> Parse<T> {
     }
 }
 
+impl Parse<SourceFile> {
+    /// the tree after `edit`, only what it touches is parsed again
+    pub fn reparse(&self, edit: &Indel) -> Parse<SourceFile> {
+        let root = self.syntax_node();
+        match incremental_reparse(&root, edit, self.errors.to_vec()) {
+            Some((green, errors)) => Parse::new(green, errors),
+            None => {
+                let mut text = root.text().to_string();
+                edit.apply(&mut text);
+                parse_text(&text)
+            }
+        }
+    }
+}
+
 impl Parse<SyntaxNode> {
     pub fn cast<N: AstNode>(self) -> Option<Parse<N>> {
         if N::cast(self.syntax_node()).is_some() {
@@ -85,6 +103,76 @@ impl Parse<SyntaxNode> {
     }
 }
 
+/// a record is 8 fields, the format decides how wide
+const FIELDS_PER_LINE: usize = 8;
+
+/// layout of fixed format records
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Format {
+    /// 10 columns a field
+    #[default]
+    Standard,
+    /// 20 columns a field, `*KEYWORD LONG=Y` or `*PART+`
+    Long,
+    /// `*KEYWORD I10=Y` or `*NODE %`, 8 column integers widened to 10
+    I10,
+    /// `*KEYWORD LONG=Y I10=Y`, 20 columns and 10 column integers under them
+    LongI10,
+}
+
+impl Format {
+    pub fn width(self) -> usize {
+        match self {
+            Format::Standard | Format::I10 => 10,
+            Format::Long | Format::LongI10 => 20,
+        }
+    }
+
+    pub fn is_long(self) -> bool {
+        matches!(self, Format::Long | Format::LongI10)
+    }
+
+    pub fn is_i10(self) -> bool {
+        matches!(self, Format::I10 | Format::LongI10)
+    }
+
+    fn of(long: bool, i10: bool) -> Format {
+        match (long, i10) {
+            (false, false) => Format::Standard,
+            (true, false) => Format::Long,
+            (false, true) => Format::I10,
+            (true, true) => Format::LongI10,
+        }
+    }
+
+    /// the `+`, `-` or `%` after a keyword
+    pub fn from_suffix(suffix: &str) -> Option<Format> {
+        match suffix {
+            "+" => Some(Format::Long),
+            "-" => Some(Format::Standard),
+            "%" => Some(Format::I10),
+            _ => None,
+        }
+    }
+
+    /// after `*KEYWORD LONG=Y I10=Y`, memory sizes and the like are ignored.
+    /// the two are set apart, one doesn't undo the other
+    pub fn with_options(self, args: &str) -> Format {
+        let (mut long, mut i10) = (self.is_long(), self.is_i10());
+        for arg in args.split_whitespace() {
+            let Some((key, val)) = arg.split_once('=') else {continue};
+            let on = val.eq_ignore_ascii_case("Y");
+            match key.to_ascii_uppercase().as_str() {
+                "LONG" if on => long = true,
+                "LONG" if val.eq_ignore_ascii_case("S") => long = false,
+                "I10" => i10 = on,
+                _ => {}
+            }
+        }
+        Format::of(long, i10)
+    }
+}
+
 // actually only used in a function
 pub struct CstParser<'a> {
     text: &'a str,
@@ -93,6 +181,14 @@ pub struct CstParser<'a> {
     tokens: Vec<(SyntaxKind, TextRange)>,
     /// the in-progress tree.
     builder: SyntaxTreeBuilder,
+    /// set by `*KEYWORD`, for every card after
+    format: Format,
+    /// format of the card being parsed
+    card_format: Format,
+    /// column widths of the card being parsed
+    card_widths: Vec<usize>,
+    /// records still to come that are text, commas in them are not free format
+    text_records: usize,
 }
 
 impl<'a> CstParser<'a> {
@@ -111,70 +207,224 @@ impl<'a> CstParser<'a> {
             text,
             tokens,
             builder: SyntaxTreeBuilder::default(),
+            format: Format::default(),
+            card_format: Format::default(),
+            card_widths: vec![],
+            text_records: 0,
         }
     }
 
-    fn node_from_line_a(&mut self, kind: SyntaxKind) {
-        self.builder.start_node(kind);
-        self.skip_comment();
-        loop {
-            match self.current() {
-                Some(NEWLINE) => {
-                    self.bump();
-                    break;
-                }
-                None => {
-                    // // eof error
-                    // let err = String::from("reaching EOF too soon");
-                    // let pos = self.text.len();
-                    // self.builder.error(err, pos.try_into().unwrap());
-                    break;
-                }
-                _ => self.bump(),
-            }
+    /// `*NAME`, an optional format suffix, then whatever arguments
+    fn keyword(&mut self) {
+        self.builder.start_node(KEYWORD);
+        let (line, newline) = self.take_line();
+        let text = &self.text[line];
+        let (name, suffix, args) = split_keyword(text);
+        self.builder.token(ASTERISK, "*");
+        lex_into(&mut self.builder, name);
+        // the suffix, with the spaces before it if it stands alone
+        lex_into(&mut self.builder, &text[1 + name.len()..text.len() - args.len()]);
+        lex_into(&mut self.builder, args);
+        if !newline.is_empty() {
+            self.builder.token(NEWLINE, &self.text[newline]);
         }
         self.builder.finish_node();
+
+        let kw = KeywordName::parse(name);
+        self.card_format = card_format(&mut self.format, name, suffix, args);
+        self.card_widths = card_widths(&kw, self.card_format);
+        self.text_records = text_records(&kw);
     }
 
     fn records(&mut self) {
         self.builder.start_node(RECORDS);
-        self.skip_comment();
         loop {
+            self.skip_comment_before_record();
             match self.current() {
-                Some(ASTERISK) | None | Some(NODE) | Some(ELEMENT) | Some(END) => {
+                Some(ASTERISK) | None | Some(END) => {
                     break;
                 }
-                _ => self.bump(),
+                _ => self.record(),
             }
         }
         self.builder.finish_node();
     }
 
+    /// one data line, sliced on the fixed-width grid,
+    /// with the `$#` header and comments right above it
+    fn record(&mut self) {
+        self.builder.start_node(RECORD);
+        self.skip_comment();
+        let (line, newline) = self.take_line();
+        self.fields(line);
+        self.text_records = self.text_records.saturating_sub(1);
+        if !newline.is_empty() {
+            self.builder.token(NEWLINE, &self.text[newline]);
+        }
+        self.builder.finish_node();
+    }
+
+    /// `$#    lcid      sidr`, every name as a COLUMN token
+    fn header(&mut self) {
+        let (_, range) = self.tokens.pop().unwrap();
+        let text = &self.text[range];
+        let content = text.trim_end_matches(['\n', '\r']);
+        self.builder.start_node(HEADER);
+        self.builder.token(COMMENT, &content[..2]);
+        let mut rest = &content[2..];
+        while !rest.is_empty() {
+            let spaces = rest.len() - rest.trim_start_matches(' ').len();
+            let (run, kind) = match spaces {
+                0 => (rest.find(' ').unwrap_or(rest.len()), COLUMN),
+                n => (n, WHITESPACE),
+            };
+            self.builder.token(kind, &rest[..run]);
+            rest = &rest[run..];
+        }
+        if content.len() < text.len() {
+            self.builder.token(NEWLINE, &text[content.len()..]);
+        }
+        self.builder.finish_node();
+    }
+
+    /// pop every token of current line, the text is cut again by caller.
+    /// returns (content, line break)
+    fn take_line(&mut self) -> (TextRange, TextRange) {
+        let range = |a: usize, b: usize| TextRange::new(a.try_into().unwrap(), b.try_into().unwrap());
+        let start = self.tokens.last().map_or(self.text.len(), |t| t.1.start().into());
+        let end = match self.text[start..].find('\n') {
+            Some(i) => start + i + 1,
+            None => self.text.len(),
+        };
+        let mut last = end;
+        while let Some((_, range)) = self.tokens.last() {
+            if usize::from(range.start()) >= end {
+                break;
+            }
+            last = last.max(range.end().into());
+            self.tokens.pop();
+        }
+        // `*END` in the middle of a line runs on to the next `*`, lex that part again
+        if last > end {
+            let at = TextSize::try_from(end).unwrap();
+            let mut rest: Vec<_> = SyntaxKind::lexer(&self.text[end..last])
+                .spanned()
+                .map(|(kind, span)| (kind, range(span.start, span.end) + at))
+                .collect();
+            rest.reverse();
+            self.tokens.extend(rest);
+        }
+        let content = self.text[start..end].trim_end_matches(['\n', '\r']).len();
+        (range(start, start + content), range(start + content, end))
+    }
+
+    /// columns as wide as the card says, a column the line doesn't reach
+    /// is not there at all, a blank column is an empty FIELD
+    fn fields(&mut self, line: TextRange) {
+        let text = &self.text[line];
+        if self.text_records == 0 && text.contains(',') {
+            self.free_fields(text);
+            return;
+        }
+        let mut bounds = Vec::with_capacity(self.card_widths.len());
+        let mut col = 0;
+        for &width in &self.card_widths {
+            if col >= text.len() {
+                break;
+            }
+            let end = ceil_char_boundary(text, col + width);
+            bounds.push((col, end));
+            col = end;
+        }
+        self.overflows(line, &bounds);
+        for &(a, b) in &bounds {
+            self.builder.start_node(FIELD);
+            lex_into(&mut self.builder, &text[a..b]);
+            self.builder.finish_node();
+        }
+        if col < text.len() {
+            let rest = &text[col..];
+            if !rest.trim().is_empty() {
+                let at = line.start() + TextSize::try_from(col).unwrap();
+                let rng = TextRange::at(at, TextSize::of(rest));
+                self.builder
+                    .range_error("text beyond the last column is ignored".to_string(), rng);
+            }
+            self.builder.start_node(ERROR);
+            lex_into(&mut self.builder, rest);
+            self.builder.finish_node();
+        }
+    }
+
+    /// `1,2,3,,0.5`, same FIELDs as fixed format with commas in between
+    fn free_fields(&mut self, text: &str) {
+        for (i, field) in text.split(',').enumerate() {
+            if i > 0 {
+                self.builder.token(COMMA, ",");
+            }
+            self.builder.start_node(FIELD);
+            lex_into(&mut self.builder, field);
+            self.builder.finish_node();
+        }
+    }
+
+    /// a number crossing a column boundary is read as two by the solver,
+    /// unless it starts right at its own column, then it's just two packed values
+    fn overflows(&mut self, line: TextRange, bounds: &[(usize, usize)]) {
+        let text = self.text[line].as_bytes();
+        let mut checked = 0;
+        for &(_, b) in bounds {
+            if b < checked || b >= text.len() || text[b - 1] == b' ' || text[b] == b' ' {
+                continue;
+            }
+            let lo = text[..b].iter().rposition(|c| *c == b' ').map_or(0, |i| i + 1);
+            let hi = text[b..].iter().position(|c| *c == b' ').map_or(text.len(), |i| b + i);
+            checked = hi;
+            let Some(&(a, z)) = bounds.iter().find(|&&(a, z)| a <= lo && lo < z) else {continue};
+            if a == lo {
+                continue;
+            }
+            let (run, width) = (&self.text[line][lo..hi], z - a);
+            if run.parse::<f64>().is_ok() {
+                let at = line.start() + TextSize::try_from(lo).unwrap();
+                self.builder.range_error(
+                    format!("`{run}` overflows its {width}-column field"),
+                    TextRange::at(at, TextSize::of(run)),
+                );
+            }
+        }
+    }
+
     fn card(&mut self) {
         debug_assert!(self.current() == Some(ASTERISK));
         self.builder.start_node(CARD); // 1
         self.skip_comment();
-        self.node_from_line_a(KEYWORD);
-        if self.current() == Some(ASTERISK) {
-            self.builder.error(
-                "new card in card!".to_string(),
-                self.tokens.last().unwrap().1.start(),
-            );
-            self.builder.finish_node();
-            self.card(); // little recurse
-            return;
+        self.keyword();
+        self.skip_comment_before_record();
+        match self.current() {
+            Some(ASTERISK) => {
+                self.builder.error(
+                    "new card in card!".to_string(),
+                    self.tokens.last().unwrap().1.start(),
+                );
+                self.builder.finish_node();
+                return;
+            }
+            None | Some(END) => {
+                self.builder.finish_node();
+                return;
+            }
+            _ => {}
         }
         self.builder.start_node(DECK); // 2
-        self.skip_comment();
-        self.node_from_line_a(RECORD);
-        self.skip_comment();
+        self.record();
         self.records();
         self.builder.finish_node();
         self.builder.finish_node();
     }
 
     pub fn parse(mut self) -> Parse<SourceFile> {
-        if self.current() == None {
+        if self.current().is_none() {
             self.builder
                 .error("file has no content".to_string(), TextSize::default());
         }
@@ -185,11 +435,12 @@ impl<'a> CstParser<'a> {
                 Some(ASTERISK) => {
                     self.card();
                 }
-                None | Some(END) => break,
-                Some(NODE) | Some(ELEMENT) => {
-                    self.builder.start_node(GEOMETRY);
-                    self.bump();
-                    self.builder.finish_node();
+                None => break,
+                // the solver stops reading here, keep the rest as is
+                Some(END) => {
+                    while self.current().is_some() {
+                        self.bump();
+                    }
                 }
                 _ => {
                     let err = String::from("what is?");
@@ -211,20 +462,81 @@ impl<'a> CstParser<'a> {
         }
     }
 
-    fn skip_some(&mut self, some: &[SyntaxKind]) {
-        while some.contains(&self.current().unwrap_or(EOF)) {
-            self.bump()
+    /// cards of a piece of file, ending right before a token of kind `next`
+    /// or at the end of file. `None` if the text is not cards only
+    pub(crate) fn parse_cards(
+        mut self,
+        format: Format,
+        next: Option<SyntaxKind>,
+    ) -> Option<(GreenNode, Vec<SyntaxError>)> {
+        // what comes after decides whether a card is cut short
+        if let Some(kind) = next {
+            let end = TextSize::of(self.text);
+            self.tokens.insert(0, (kind, TextRange::empty(end)));
+        }
+        let rest = usize::from(next.is_some());
+        self.format = format;
+        if self.current() != Some(ASTERISK) {
+            return None;
         }
+        self.builder.start_node(ROOT);
+        while self.current() == Some(ASTERISK) && self.tokens.len() > rest {
+            self.card();
+        }
+        self.builder.finish_node();
+        if self.tokens.len() != rest {
+            return None;
+        }
+        Some(self.builder.finish_raw())
+    }
+
+    /// the fields of a single data line, in a RECORD of their own
+    /// `text` for a title or heading, see [`text_records`]
+    pub(crate) fn parse_line(mut self, widths: Vec<usize>, text: bool) -> (GreenNode, Vec<SyntaxError>) {
+        self.card_widths = widths;
+        self.text_records = text as usize;
+        self.builder.start_node(RECORD);
+        self.fields(TextRange::up_to(TextSize::of(self.text)));
+        self.builder.finish_node();
+        self.builder.finish_raw()
     }
 
     fn skip_comment(&mut self) {
-        self.skip_some(&[COMMENT])
+        while self.current() == Some(COMMENT) {
+            match self.at_header() {
+                true => self.header(),
+                false => self.bump(),
+            }
+        }
+    }
+
+    /// same, but a header goes with the record below, so stop there
+    fn skip_comment_before_record(&mut self) {
+        while self.current() == Some(COMMENT) && !self.header_of_record() {
+            match self.at_header() {
+                true => self.header(),
+                false => self.bump(),
+            }
+        }
+    }
+
+    fn at_header(&self) -> bool {
+        match self.tokens.last() {
+            Some((COMMENT, range)) => self.text[*range].starts_with("$#"),
+            _ => false,
+        }
+    }
+
+    /// a header, and after whatever comments comes a data line
+    fn header_of_record(&self) -> bool {
+        let next = self.tokens.iter().rev().map(|t| t.0).find(|k| *k != COMMENT);
+        self.at_header() && !matches!(next, Some(ASTERISK) | Some(END) | None)
     }
 
     // so that's what you call lossless, it caches str
     fn bump(&mut self) {
         let (kind, range) = self.tokens.pop().unwrap();
-        self.builder.token(kind.into(), &self.text[range]);
+        self.builder.token(kind, &self.text[range]);
     }
 
     fn current(&self) -> Option<SyntaxKind> {
@@ -232,8 +544,261 @@ impl<'a> CstParser<'a> {
     }
 }
 
+/// mesh cards are not on the even grid, integers and reals differ in width
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Col {
+    Int,
+    Real,
+}
+
+impl Col {
+    fn width(self, format: Format) -> usize {
+        match (self, format) {
+            (_, Format::Long | Format::LongI10) => 20,
+            (Col::Int, Format::Standard) => 8,
+            (Col::Int, Format::I10) => 10,
+            (Col::Real, _) => 16,
+        }
+    }
+}
+
+/// nid, x, y, z, tc, rc
+const NODE_COLS: &[Col] = &[Col::Int, Col::Real, Col::Real, Col::Real, Col::Int, Col::Int];
+/// eid, pid, n1..n8
+const ELEMENT_COLS: &[Col] = &[Col::Int; 10];
+/// eid, pid, n1, n2, vid, s, pf, offset
+const DISCRETE_COLS: &[Col] = &[
+    Col::Int,
+    Col::Int,
+    Col::Int,
+    Col::Int,
+    Col::Int,
+    Col::Real,
+    Col::Int,
+    Col::Real,
+];
+/// eid, nid, mass, pid
+const MASS_COLS: &[Col] = &[Col::Int, Col::Int, Col::Real, Col::Int];
+
+/// only the plain mesh keywords, `*NODE_MERGE_SET` and friends are ordinary cards
+fn mesh_columns(name: &KeywordName) -> Option<&'static [Col]> {
+    if !name.options().is_empty() {
+        return None;
+    }
+    match name.base() {
+        "NODE" => Some(NODE_COLS),
+        "ELEMENT_SHELL" | "ELEMENT_SOLID" | "ELEMENT_TSHELL" | "ELEMENT_BEAM" => {
+            Some(ELEMENT_COLS)
+        }
+        "ELEMENT_DISCRETE" => Some(DISCRETE_COLS),
+        "ELEMENT_MASS" => Some(MASS_COLS),
+        _ => None,
+    }
+}
+
+/// `*NAME+ args` into name, suffix and args, the suffix may also stand
+/// alone after the name, `*NODE %`. args is always the tail of the line
+pub(crate) fn split_keyword(text: &str) -> (&str, &str, &str) {
+    let name_end = text.find(char::is_whitespace).unwrap_or(text.len());
+    let (name, suffix) = match text[..name_end].char_indices().last() {
+        Some((i, c)) if i > 1 && matches!(c, '+' | '-' | '%') => (&text[1..i], &text[i..name_end]),
+        _ => (&text[1..name_end], ""),
+    };
+    let args = &text[name_end..];
+    let flag = args.trim_start();
+    if suffix.is_empty() && matches!(flag.split_whitespace().next(), Some("+" | "-" | "%")) {
+        return (name, &flag[..1], &flag[1..]);
+    }
+    (name, suffix, args)
+}
+
+/// format of the card, `*KEYWORD` also changes the one of every card after
+pub(crate) fn card_format(format: &mut Format, name: &str, suffix: &str, args: &str) -> Format {
+    if name.eq_ignore_ascii_case("KEYWORD") {
+        *format = format.with_options(args);
+    }
+    Format::from_suffix(suffix).unwrap_or(*format)
+}
+
+/// column widths of every record of the card
+pub(crate) fn card_widths(name: &KeywordName, format: Format) -> Vec<usize> {
+    match mesh_columns(name) {
+        Some(cols) => cols.iter().map(|c| c.width(format)).collect(),
+        None => vec![format.width(); FIELDS_PER_LINE],
+    }
+}
+
+/// how many records of the card, from the first, are text: titles, headings and
+/// comments. a comma there is part of the text
+pub(crate) fn text_records(name: &KeywordName) -> usize {
+    match name.base() {
+        "TITLE" | "COMMENT" => usize::MAX,
+        "PART" => 1,
+        _ if name.has(KwOption::Title) || name.has(KwOption::Id) => 1,
+        _ => 0,
+    }
+}
+
+/// tokens of a piece of line, pushed as is
+fn lex_into(builder: &mut SyntaxTreeBuilder, text: &str) {
+    for (kind, span) in SyntaxKind::lexer(text).spanned() {
+        builder.token(kind, &text[span]);
+    }
+}
+
+fn ceil_char_boundary(text: &str, mut i: usize) -> usize {
+    if i >= text.len() {
+        return text.len();
+    }
+    while !text.is_char_boundary(i) {
+        i += 1;
+    }
+    i
+}
+
 // my first play with phantom data
 pub fn parse_text(text: &str) -> Parse<SourceFile> {
     let parser = CstParser::new(text);
     parser.parse()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_text, Format};
+    use crate::ast::AstNode;
+
+    /// text of every field of the first record of the last card
+    fn fields(text: &str) -> Vec<String> {
+        let file = parse_text(text).tree();
+        let card = file.cards().last().unwrap();
+        let record = card.records().next().unwrap();
+        record.fields().map(|f| f.syntax().text().to_string()).collect()
+    }
+
+    #[test]
+    fn long_and_i10_together() {
+        assert_eq!(Format::Standard.with_options("LONG=Y I10=Y"), Format::LongI10);
+        assert_eq!(Format::Standard.with_options("I10=Y LONG=Y"), Format::LongI10);
+        assert_eq!(Format::LongI10.with_options("I10=N"), Format::Long);
+        assert_eq!(Format::LongI10.with_options("LONG=S"), Format::I10);
+        assert_eq!(Format::Long.with_options("I10=N"), Format::Long);
+        assert_eq!(Format::I10.with_options("MEMORY=20M"), Format::I10);
+    }
+
+    #[test]
+    fn long_i10_keeps_long_columns() {
+        let record = format!("{:>20}{:>20}", 1, "0.0");
+        let text = format!("*KEYWORD LONG=Y I10=Y\n*NODE\n{record}\n");
+        assert_eq!(parse_text(&text).tree().format(), Format::LongI10);
+        assert_eq!(fields(&text), vec![&record[..20], &record[20..]]);
+        let text = "*KEYWORD I10=Y LONG=Y\n*PART\n\n                   1                   2\n";
+        let file = parse_text(text).tree();
+        let card = file.cards().last().unwrap();
+        let record = card.records().nth(1).unwrap();
+        assert_eq!(record.fields().count(), 2);
+    }
+
+    #[test]
+    fn commas_in_titles_are_text() {
+        let title = |text: &str| parse_text(text).tree().cards().last().unwrap().records().next().unwrap();
+        assert!(!title("*TITLE\ncar, front crash\n").is_free_format());
+        assert!(!title("*PART\nbumper, left\n1,2,3\n").is_free_format());
+        assert!(!title("*SECTION_SHELL_TITLE\nshell, thin\n1,2,0.8\n").is_free_format());
+        assert!(title("*SECTION_SHELL\n1,2,0.8\n").is_free_format());
+        // the records after the heading still may be
+        let text = "*PART\nbumper, left\n1,2,3\n";
+        let card = parse_text(text).tree().cards().last().unwrap();
+        assert!(card.records().nth(1).unwrap().is_free_format());
+        assert_eq!(fields("*PART\nbumper, left\n"), vec!["bumper, le", "ft"]);
+    }
+
+    fn errors(text: &str) -> Vec<String> {
+        parse_text(text).errors.iter().map(|e| e.to_string()).collect()
+    }
+
+    #[test]
+    fn fixed_width_fields() {
+        let text = "*SECTION_SHELL\n         1         2     0.833\n";
+        assert_eq!(fields(text), vec!["         1", "         2", "     0.833"]);
+        // a blank column is an empty field, one the line doesn't reach is none
+        assert_eq!(fields("*PART\nbumper\n"), vec!["bumper"]);
+        let record = "         1                   3";
+        let file = parse_text(&format!("*SECTION_SHELL\n{record}\n")).tree();
+        let record = file.cards().next().unwrap().records().next().unwrap();
+        let values: Vec<_> = record.fields().map(|f| f.value()).collect();
+        assert_eq!(values, vec![Some("1".to_string()), None, Some("3".to_string())]);
+        assert!(record.field(3).is_none());
+        // lossless, line breaks and all
+        let text = "*SECTION_SHELL\r\n         1\r\n";
+        assert_eq!(parse_text(text).syntax_node().text().to_string(), text);
+    }
+
+    #[test]
+    fn overflowing_and_trailing_text() {
+        let text = "*SECTION_SHELL\n         1  12345678\n";
+        assert!(errors(text).is_empty());
+        let text = "*SECTION_SHELL\n         1 123456789012\n";
+        assert_eq!(errors(text), vec!["`123456789012` overflows its 10-column field"]);
+        let long = format!("*SECTION_SHELL\n{}extra\n", "         1".repeat(8));
+        assert_eq!(errors(&long), vec!["text beyond the last column is ignored"]);
+    }
+
+    #[test]
+    fn node_and_element_records() {
+        let node = format!("{:>8}{:>16}{:>16}{:>16}{:>8}{:>8}", 1, "0.0", "1.5", "-2.", 0, 0);
+        let shell = format!("{:>8}{:>8}{:>8}{:>8}{:>8}{:>8}", 10, 1, 1, 2, 3, 4);
+        let text = format!("*NODE\n{node}\n{node}\n*ELEMENT_SHELL\n{shell}\n");
+        let file = parse_text(&text).tree();
+        let cards: Vec<_> = file.cards().collect();
+        assert_eq!(cards[0].records().count(), 2);
+        let widths: Vec<_> = fields(&format!("*NODE\n{node}\n")).iter().map(|f| f.len()).collect();
+        assert_eq!(widths, vec![8, 16, 16, 16, 8, 8]);
+        assert_eq!(fields(&text), vec!["      10", "       1", "       1", "       2", "       3", "       4"]);
+        // `%` widens the integers only
+        let node = format!("{:>10}{:>16}", 1, "0.0");
+        assert_eq!(fields(&format!("*NODE %\n{node}\n")), vec![&node[..10], &node[10..]]);
+    }
+
+    #[test]
+    fn other_node_keywords_are_ordinary_cards() {
+        let text = "*NODE_MERGE_SET\n         1       0.1\n";
+        assert_eq!(fields(text), vec!["         1", "       0.1"]);
+        let text = "*NODE_TRANSFORM\n         1         2\n*NODE\n       5\n";
+        let file = parse_text(text).tree();
+        let names: Vec<_> = file.cards().map(|c| c.keyword().unwrap().name().to_string()).collect();
+        assert_eq!(names, vec!["NODE_TRANSFORM", "NODE"]);
+        assert_eq!(fields(text), vec!["       5"]);
+    }
+
+    #[test]
+    fn headers_go_with_the_record_below() {
+        let text = "*SECTION_SHELL\n$#   secid    elform  time [s]\n         1         2       0.0\n";
+        let file = parse_text(text).tree();
+        let record = file.cards().next().unwrap().records().next().unwrap();
+        let columns = record.header().unwrap().columns();
+        let names: Vec<_> = columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["secid", "elform", "time [s]"]);
+        let spans: Vec<_> = columns.iter().map(|c| (c.start, c.end)).collect();
+        assert_eq!(spans, vec![(0, 10), (10, 20), (20, 30)]);
+        assert!(record.misaligned_columns().is_empty());
+        // the data line alone
+        let line = &text[usize::from(record.line_range().start())..usize::from(record.line_range().end())];
+        assert_eq!(line, "         1         2       0.0");
+    }
+
+    #[test]
+    fn misaligned_headers() {
+        let text = "*SECTION_SHELL\n$#   secid  elform\n         1         2\n";
+        let file = parse_text(text).tree();
+        let record = file.cards().next().unwrap().records().next().unwrap();
+        let names: Vec<_> = record.misaligned_columns().into_iter().map(|c| c.name).collect();
+        assert_eq!(names, vec!["elform"]);
+        // columns past the line are not checked, nor free format
+        let text = "*SECTION_SHELL\n$#   secid    elform   shrf\n         1\n";
+        let record = parse_text(text).tree().cards().next().unwrap().records().next().unwrap();
+        assert!(record.misaligned_columns().is_empty());
+        let text = "*SECTION_SHELL\n$#   secid  elform\n1,2\n";
+        let record = parse_text(text).tree().cards().next().unwrap().records().next().unwrap();
+        assert!(record.misaligned_columns().is_empty());
+    }
+}
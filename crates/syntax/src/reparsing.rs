@@ -1,37 +1,299 @@
-use rowan::{GreenNode, TextRange};
+//! parse again only what an edit touches: the data line when the edit stays
+//! inside one, else the cards around it. `None` when the edit changes more
+//! than that, then it's a full parse
+
+use rowan::{GreenNode, NodeOrToken, TextRange, TextSize};
 use text_edit::Indel;
 
-use crate::{syntax_error::SyntaxError, syntax_node::SyntaxNode};
-
-pub fn reparse_card(root: &SyntaxNode, edit: &Indel) -> Option<(GreenNode, Vec<SyntaxError>)> {
-    use crate::syntax_node::SyntaxKind::{self, *};
-    let mut err = vec![];
-    let green;
-    let current_node = root.covering_element(edit.delete).as_node()?.clone();
-    let mom_is = |c: SyntaxKind| current_node.ancestors().find(|node| node.kind() == c);
-
-    if let Some(node) = mom_is(GEOMETRY) {
-        err.push(SyntaxError::new(
-            "don't edit geometry yet, naughty!".to_string(),
-            TextRange::default(),
-        ))
-    }
-
-    if let Some(node) = mom_is(CARD) {
-        err.push(SyntaxError::new(
-            "got a card!".to_string(),
-            TextRange::empty(node.text_range().start()),
-        ));
-    } else {
-        for i in current_node.ancestors() {
-            let duh = i.kind();
-            err.push(SyntaxError::new(
-                format!("{:?}", duh),
-                TextRange::empty(current_node.text_range().start()),
-            ));
+use crate::{
+    ast::AstNode,
+    dyna_nodes::{Card, Record},
+    keyword::KeywordName,
+    parse::{card_format, card_widths, split_keyword, text_records, CstParser, Format},
+    syntax_error::SyntaxError,
+    syntax_node::{SyntaxElement, SyntaxKind::*, SyntaxNode},
+};
+
+/// new root and errors after `edit`, `errors` are those of `root`
+pub fn incremental_reparse(
+    root: &SyntaxNode,
+    edit: &Indel,
+    errors: Vec<SyntaxError>,
+) -> Option<(GreenNode, Vec<SyntaxError>)> {
+    let (green, new_errors, owned) =
+        reparse_record(root, edit).or_else(|| reparse_card(root, edit))?;
+    Some((green, merge_errors(errors, new_errors, owned, edit)))
+}
+
+/// the edit stays inside one data line and leaves it a data line.
+/// gives back the errors found and where the old ones are replaced by them
+fn reparse_record(
+    root: &SyntaxNode,
+    edit: &Indel,
+) -> Option<(GreenNode, Vec<SyntaxError>, TextRange)> {
+    let node = match root.covering_element(edit.delete) {
+        NodeOrToken::Node(node) => node,
+        NodeOrToken::Token(token) => token.parent()?,
+    };
+    let record = node.ancestors().find_map(Record::cast)?;
+    let line = record.line_range();
+    if !line.contains_range(edit.delete) {
+        return None;
+    }
+    let record_text = record.syntax().text().to_string();
+    let offset = line.start() - record.syntax().text_range().start();
+    let mut text = record_text[TextRange::at(offset, line.len())].to_string();
+    let old_line_bad = text.contains('*');
+    Indel::replace(edit.delete - line.start(), edit.insert.clone()).apply(&mut text);
+    // a `*` may start a keyword or an `*END`, `$` a comment
+    if old_line_bad || text.starts_with('$') || text.contains(['*', '\n', '\r']) {
+        return None;
+    }
+    // nothing left on the last line, no record at all
+    if text.is_empty() && line.end() == root.text_range().end() {
+        return None;
+    }
+
+    let card = record.syntax().ancestors().find_map(Card::cast)?;
+    let kw = card.keyword()?;
+    let kw_line = kw.syntax().text().to_string();
+    let (name, suffix, args) = split_keyword(kw_line.trim_end_matches(['\n', '\r']));
+    let format = card_format(&mut format_before(root, card.syntax()), name, suffix, args);
+    let name = KeywordName::parse(name);
+    let widths = card_widths(&name, format);
+    let nth = card.records().position(|r| r.syntax() == record.syntax())?;
+
+    let (fields, errors) = CstParser::new(&text).parse_line(widths, nth < text_records(&name));
+    // keep the header and comments above, and the line break
+    let green = record.syntax().green();
+    let kept = [HEADER, COMMENT, NEWLINE].map(rowan::SyntaxKind::from);
+    let data: Vec<usize> = green
+        .children()
+        .enumerate()
+        .filter(|(_, c)| !kept.contains(&c.kind()))
+        .map(|(i, _)| i)
+        .collect();
+    let at = match data.first() {
+        Some(&i) => i,
+        None => green.children().take_while(|c| c.kind() != kept[2]).count(),
+    };
+    let new_children = fields.children().map(|c| c.to_owned());
+    let new_record = green.splice_children(at..at + data.len(), new_children);
+    let errors = shift(errors, line.start());
+    Some((record.syntax().replace_with(new_record), errors, line))
+}
+
+/// the cards touching the edit, parsed again in one go
+fn reparse_card(
+    root: &SyntaxNode,
+    edit: &Indel,
+) -> Option<(GreenNode, Vec<SyntaxError>, TextRange)> {
+    let children: Vec<SyntaxElement> = root.children_with_tokens().collect();
+    let del = edit.delete;
+    // touching counts, so the text right before and after the span is left as it was
+    let touched = |c: &SyntaxElement| {
+        c.text_range().start() <= del.end() && del.start() <= c.text_range().end()
+    };
+    let first = children.iter().position(touched)?;
+    let last = children.iter().rposition(touched)?;
+    let span = &children[first..=last];
+    if span.iter().any(|c| c.kind() != CARD) {
+        return None;
+    }
+    let range = span[0]
+        .text_range()
+        .cover(span[span.len() - 1].text_range());
+    let next = match children.get(last + 1).map(|c| c.kind()) {
+        None => None,
+        Some(CARD) => Some(ASTERISK),
+        Some(END) => Some(END),
+        Some(_) => return None,
+    };
+
+    let mut text: String = span.iter().map(|c| c.to_string()).collect();
+    let old_has_end = text.contains("*END");
+    Indel::replace(del - range.start(), edit.insert.clone()).apply(&mut text);
+    // `*END` takes everything after it
+    if old_has_end || text.contains("*END") {
+        return None;
+    }
+    let format = format_before(root, span[0].as_node()?);
+    let (cards, errors) = CstParser::new(&text).parse_cards(format, next)?;
+    // a `*KEYWORD` changed, the cards after are read another way
+    let old_cards = span
+        .iter()
+        .filter_map(|c| c.as_node().cloned())
+        .filter_map(Card::cast);
+    let new_cards = SyntaxNode::new_root(cards.clone())
+        .children()
+        .filter_map(Card::cast);
+    if format_after(format, old_cards) != format_after(format, new_cards) {
+        return None;
+    }
+    let new_children = cards.children().map(|c| c.to_owned());
+    let green = root.green().splice_children(first..=last, new_children);
+    // an error at the first `*` is the card before's, one at the next `*` is ours
+    let one = TextSize::from(1);
+    let owned = TextRange::new(range.start() + one, range.end() + one);
+    Some((green, shift(errors, range.start()), owned))
+}
+
+/// what the `*KEYWORD` cards before `card` leave as format
+fn format_before(root: &SyntaxNode, card: &SyntaxNode) -> Format {
+    let cards = root
+        .children()
+        .take_while(|c| c != card)
+        .filter_map(Card::cast);
+    format_after(Format::default(), cards)
+}
+
+fn format_after(mut format: Format, cards: impl Iterator<Item = Card>) -> Format {
+    for c in cards {
+        let Some(kw) = c.keyword() else {continue};
+        let line = kw.syntax().text().to_string();
+        let (name, suffix, args) = split_keyword(line.trim_end_matches(['\n', '\r']));
+        card_format(&mut format, name, suffix, args);
+    }
+    format
+}
+
+fn shift(errors: Vec<SyntaxError>, by: TextSize) -> Vec<SyntaxError> {
+    errors
+        .into_iter()
+        .map(|e| {
+            let range = e.range() + by;
+            e.with_range(range)
+        })
+        .collect()
+}
+
+/// old errors before `owned`, the new ones, then the old ones after, moved
+/// by how much the edit grew or shrank the text. old errors starting in
+/// `owned` are the ones parsed again
+fn merge_errors(
+    old: Vec<SyntaxError>,
+    new: Vec<SyntaxError>,
+    owned: TextRange,
+    edit: &Indel,
+) -> Vec<SyntaxError> {
+    let (inserted, deleted) = (TextSize::of(&edit.insert), edit.delete.len());
+    let (mut merged, after): (Vec<_>, Vec<_>) = old
+        .into_iter()
+        .filter(|e| !owned.contains(e.range().start()))
+        .partition(|e| e.range().start() < owned.start());
+    merged.extend(new);
+    merged.extend(after.into_iter().map(|e| {
+        let r = e.range();
+        e.with_range(TextRange::at(r.start() - deleted + inserted, r.len()))
+    }));
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+    use text_edit::Indel;
+
+    use super::incremental_reparse;
+    use crate::parse::{parse_text, TextRange, TextSize};
+
+    /// lines decks are made of, every kind of card and record the parser knows
+    const LINES: &[&str] = &[
+        "*KEYWORD\n",
+        "*KEYWORD LONG=Y\n",
+        "*KEYWORD I10=Y\n",
+        "*PART\n",
+        "*PART+\n",
+        "*SECTION_SHELL_TITLE\n",
+        "*TITLE\n",
+        "a title, with a comma\n",
+        "*NODE %\n",
+        "*NODE\n",
+        "*ELEMENT_SHELL\n",
+        "*END\n",
+        "$ a comment\n",
+        "$#     pid       sid      mid\n",
+        "$#   eid     pid      n1      n2\n",
+        "\n",
+        "title of it\n",
+        "         1         2         3\n",
+        "1,2,,0.5\n",
+        "       1       1       1       2       3       4\n",
+        "       1             0.0             1.0        -2.5e+01       0       0\n",
+        "       1      0.01.00000000\n",
+        "         1         2         3         4         5         6         7         8         9\n",
+        "  1 *END of line\n",
+        "\r\n",
+    ];
+
+    fn deck() -> impl Strategy<Value = String> {
+        prop::collection::vec(prop::sample::select(LINES), 0..24).prop_map(|lines| lines.concat())
+    }
+
+    fn insert() -> impl Strategy<Value = String> {
+        prop_oneof![
+            "[ 0-9.,e+-]{0,6}",
+            "[ *$#,\n0-9A-Z_%]{0,12}",
+            prop::sample::select(LINES).prop_map(String::from),
+        ]
+    }
+
+    /// a deck and an edit on it
+    fn edited() -> impl Strategy<Value = (String, Indel)> {
+        (deck(), any::<prop::sample::Index>(), 0..40usize, insert()).prop_map(
+            |(text, at, len, insert)| {
+                let start = at.index(text.len() + 1);
+                let end = (start + len).min(text.len());
+                let range =
+                    TextRange::new(TextSize::from(start as u32), TextSize::from(end as u32));
+                (text, Indel::replace(range, insert))
+            },
+        )
+    }
+
+    proptest! {
+        #[test]
+        fn lossless(text in deck()) {
+            prop_assert_eq!(parse_text(&text).syntax_node().text().to_string(), text);
+        }
+
+        #[test]
+        fn reparse_is_full_parse((text, edit) in edited()) {
+            let mut after = text.clone();
+            edit.apply(&mut after);
+            let reparsed = parse_text(&text).reparse(&edit);
+            let full = parse_text(&after);
+            prop_assert_eq!(reparsed.green, full.green);
+            prop_assert_eq!(reparsed.errors, full.errors);
+        }
+
+        #[test]
+        fn edits_in_a_row(text in deck(), edits in prop::collection::vec((any::<prop::sample::Index>(), 0..8usize, insert()), 1..8)) {
+            let mut text = text;
+            let mut parse = parse_text(&text);
+            for (at, len, insert) in edits {
+                let start = at.index(text.len() + 1);
+                let end = (start + len).min(text.len());
+                let range = TextRange::new(TextSize::from(start as u32), TextSize::from(end as u32));
+                let edit = Indel::replace(range, insert);
+                edit.apply(&mut text);
+                parse = parse.reparse(&edit);
+                let full = parse_text(&text);
+                prop_assert_eq!(&parse.green, &full.green);
+                prop_assert_eq!(&parse.errors, &full.errors);
+            }
         }
     }
-    // green = root.green().into_owned();
-    green = root.green().into_owned();
-    Some((green, err))
+
+    #[test]
+    fn typing_in_a_record_is_incremental() {
+        let text = "*PART\n$#     pid       sid\n         1         2\n*END\n";
+        let parse = parse_text(text);
+        let edit = Indel::insert(TextSize::from(36), "3".to_string());
+        let reparsed = incremental_reparse(&parse.syntax_node(), &edit, parse.errors.to_vec());
+        assert!(reparsed.is_some());
+        let edit = Indel::insert(TextSize::from(6), "*NODE\n".to_string());
+        let reparsed = incremental_reparse(&parse.syntax_node(), &edit, parse.errors.to_vec());
+        assert!(reparsed.is_some());
+    }
 }
@@ -1,6 +1,6 @@
 use logos::Logos;
-use rowan::TextSize;
-use rowan::{GreenNode, GreenNodeBuilder, Language, NodeOrToken};
+use rowan::{TextRange, TextSize};
+use rowan::{GreenNode, GreenToken, Language, NodeOrToken};
 
 use crate::syntax_error::SyntaxError;
 
@@ -17,24 +17,32 @@ pub enum SyntaxKind {
     NEWLINE,
     #[token("*")]
     ASTERISK,
-    #[regex(r"\$.*\n")]
+    #[regex(r"\$[^\n]*\n?")]
     COMMENT,
     #[token("_")]
     UNDERSCODE,
     #[regex(r"-?\d+(?:\.\d*)?(?:[eE][+-]?\d+)?")]
     NUMBER,
+    #[token("+")]
+    PLUS,
+    #[token("-")]
+    MINUS,
+    #[token("%")]
+    PERCENT,
+    #[token(",")]
+    COMMA,
+    #[regex(r"&[[:alpha:]][[:alnum:]_]*")]
+    PARAM, // `&name`, a value of *PARAMETER
+    COLUMN, // a name in `$#` header
 
-    #[regex(r"\*NODE[^\*]*")]
-    NODE,
-    #[regex(r"\*ELEMENT[^\*]*")]
-    ELEMENT,
     #[regex(r"\*END[^\*]*")]
     END,
 
-    GEOMETRY, // as long as len don't change, shouldn't bother
     CARD,     // keyword + deck
     KEYWORD,  // *PART
     RECORD,   // every keyword at least follows one record
+    FIELD,    // one column of a record, blank columns included
+    HEADER,   // `$#` names of the columns below
     RECORDS,  // 0 or many, not one
     DECK,     // RECORD + RECORDS
 
@@ -52,7 +60,7 @@ impl SyntaxKind {
 
     #[inline]
     pub fn is_trivia(self) -> bool {
-        matches!(self, SyntaxKind::COMMENT | SyntaxKind::GEOMETRY)
+        matches!(self, SyntaxKind::COMMENT)
     }
 }
 
@@ -85,42 +93,69 @@ pub type SyntaxNodeChildren = rowan::SyntaxNodeChildren<Lang>;
 pub type SyntaxElementChildren = rowan::SyntaxElementChildren<Lang>;
 pub type PreorderWithTokens = rowan::api::PreorderWithTokens<Lang>;
 
+/// no node cache like `GreenNodeBuilder`, mesh includes are millions of distinct
+/// numbers and hashing each one costs more than it saves. Only runs of spaces,
+/// the one token every record is full of, are shared.
 #[derive(Default)]
-///TODO:!
 pub struct SyntaxTreeBuilder {
     errors: Vec<SyntaxError>,
-    inner: GreenNodeBuilder<'static>,
+    /// open nodes with the children so far
+    parents: Vec<(SyntaxKind, Vec<NodeOrToken<GreenNode, GreenToken>>)>,
+    root: Option<GreenNode>,
+    /// indexed by length
+    spaces: Vec<Option<GreenToken>>,
 }
 
 impl SyntaxTreeBuilder {
     pub(crate) fn finish_raw(self) -> (GreenNode, Vec<SyntaxError>) {
-        let green = self.inner.finish();
+        assert!(self.parents.is_empty(), "unfinished node");
+        let green = self.root.expect("no root node");
         (green, self.errors)
     }
 
     pub fn token(&mut self, kind: SyntaxKind, text: &str) {
-        let kind = Lang::kind_to_raw(kind);
-        self.inner.token(kind, text);
+        let token = match kind {
+            SyntaxKind::WHITESPACE => {
+                let len = text.len();
+                if self.spaces.len() <= len {
+                    self.spaces.resize(len + 1, None);
+                }
+                self.spaces[len]
+                    .get_or_insert_with(|| GreenToken::new(Lang::kind_to_raw(kind), text))
+                    .clone()
+            }
+            _ => GreenToken::new(Lang::kind_to_raw(kind), text),
+        };
+        let (_, children) = self.parents.last_mut().expect("token outside of node");
+        children.push(NodeOrToken::Token(token));
     }
 
     pub fn start_node(&mut self, kind: SyntaxKind) {
-        let kind = Lang::kind_to_raw(kind);
-        self.inner.start_node(kind);
+        self.parents.push((kind, vec![]));
     }
 
     pub fn finish_node(&mut self) {
-        self.inner.finish_node();
+        let (kind, children) = self.parents.pop().expect("no node to finish");
+        let node = GreenNode::new(Lang::kind_to_raw(kind), children);
+        match self.parents.last_mut() {
+            Some((_, siblings)) => siblings.push(NodeOrToken::Node(node)),
+            None => self.root = Some(node),
+        }
     }
 
     pub fn error(&mut self, error: String, text_pos: TextSize) {
         self.errors
             .push(SyntaxError::new_at_offset(error, text_pos));
     }
+
+    pub fn range_error(&mut self, error: String, range: TextRange) {
+        self.errors.push(SyntaxError::new(error, range));
+    }
 }
 
 // print a node to std
 pub fn print(indent: usize, element: SyntaxElement) {
-    let kind: SyntaxKind = element.kind().into();
+    let kind: SyntaxKind = element.kind();
     print!("{:indent$}", "", indent = indent);
     match element {
         NodeOrToken::Node(node) => {